@@ -0,0 +1,604 @@
+use crate::fs::FileSystem;
+use crate::fs::Handle;
+use crate::Archive;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::Timelike;
+use std::collections::HashSet;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+const BLOCK: u64 = 512;
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const ZIP_VERSION: u16 = 20;
+const ZIP_MADE_BY_UNIX: u16 = (3 << 8) | ZIP_VERSION;
+const ZIP_UTF8: u16 = 1 << 11;
+const FILE_MODE: u32 = 0o100644;
+const LONG_NAME: &str = "././@LongLink";
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+	let mut table = [0; 256];
+	let mut n = 0;
+
+	while n < 256 {
+		let mut crc = n as u32;
+		let mut bit = 0;
+
+		while bit < 8 {
+			crc = match crc & 1 {
+				1 => 0xedb88320 ^ (crc >> 1),
+				_ => crc >> 1,
+			};
+			bit += 1;
+		}
+
+		table[n] = crc;
+		n += 1;
+	}
+
+	table
+}
+
+fn invalid(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u16_at(bytes: &[u8], idx: usize) -> io::Result<u16> {
+	bytes
+		.get(idx..idx + 2)
+		.map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+		.ok_or_else(|| invalid("Truncated zip archive".to_string()))
+}
+
+fn u32_at(bytes: &[u8], idx: usize) -> io::Result<u32> {
+	bytes
+		.get(idx..idx + 4)
+		.map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+		.ok_or_else(|| invalid("Truncated zip archive".to_string()))
+}
+
+fn text(field: &[u8]) -> String {
+	let end = field
+		.iter()
+		.position(|&byte| byte == 0)
+		.unwrap_or(field.len());
+	String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn octal(field: &[u8]) -> Option<u64> {
+	let digits = text(field);
+	u64::from_str_radix(digits.trim(), 8).ok()
+}
+
+fn put_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+	let width = field.len() - 1;
+	let digits = format!("{value:0width$o}");
+
+	match digits.len() > width {
+		true => Err(invalid(format!(
+			"Value {value} does not fit in a tar header"
+		))),
+		false => {
+			field[..width].copy_from_slice(digits.as_bytes());
+			Ok(())
+		}
+	}
+}
+
+fn split_name(name: &str) -> Option<(&str, &str)> {
+	if name.len() <= 100 {
+		return Some(("", name));
+	}
+
+	name.match_indices('/')
+		.map(|(idx, _)| (&name[..idx], &name[idx + 1..]))
+		.find(|(prefix, name)| prefix.len() <= 155 && !name.is_empty() && name.len() <= 100)
+}
+
+fn padding(size: u64) -> usize {
+	(size.div_ceil(BLOCK) * BLOCK - size) as usize
+}
+
+fn tar_header(name: &str, size: u64, modified: i64, kind: u8) -> io::Result<[u8; BLOCK as usize]> {
+	let (prefix, name) = split_name(name).unwrap_or(("", name));
+	let name = &name.as_bytes()[..name.len().min(100)];
+
+	let mut header = [0; BLOCK as usize];
+	header[..name.len()].copy_from_slice(name);
+	put_octal(&mut header[100..108], u64::from(FILE_MODE & 0o7777))?;
+	put_octal(&mut header[108..116], 0)?;
+	put_octal(&mut header[116..124], 0)?;
+	put_octal(&mut header[124..136], size)?;
+	put_octal(&mut header[136..148], modified.max(0) as u64)?;
+	header[148..156].copy_from_slice(b"        ");
+	header[156] = kind;
+	header[257..263].copy_from_slice(b"ustar\0");
+	header[263..265].copy_from_slice(b"00");
+	header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+	let checksum = header.iter().map(|&byte| u64::from(byte)).sum();
+	put_octal(&mut header[148..155], checksum)?;
+
+	Ok(header)
+}
+
+fn dos_time(modified: i64) -> (u16, u16) {
+	let date = DateTime::from_timestamp(modified, 0).map(|date| date.with_timezone(&Local));
+
+	match date {
+		Some(date) if (1980..=2107).contains(&date.year()) => (
+			((date.hour() << 11) | (date.minute() << 5) | (date.second() / 2)) as u16,
+			(((date.year() as u32 - 1980) << 9) | (date.month() << 5) | date.day()) as u16,
+		),
+		_ => (0, (1 << 5) | 1),
+	}
+}
+
+fn stream(reader: &mut dyn Read, file: &mut dyn Handle) -> io::Result<(u64, u32)> {
+	let mut buffer = vec![0; 64 * 1024];
+	let mut size = 0;
+	let mut crc = !0;
+
+	loop {
+		let read = match reader.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(read) => read,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		};
+
+		file.write_all(&buffer[..read])?;
+		crc = buffer[..read].iter().fold(crc, |crc, &byte| {
+			CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+		});
+		size += read as u64;
+	}
+
+	Ok((size, !crc))
+}
+
+pub struct Writer<'a> {
+	fs: &'a dyn FileSystem,
+	path: PathBuf,
+	file: Box<dyn Handle + 'a>,
+	created: bool,
+	end: u64,
+	tail: Option<Vec<u8>>,
+	format: Archive,
+	names: HashSet<String>,
+	central: Vec<u8>,
+	entries: u16,
+}
+
+impl<'a> Writer<'a> {
+	pub fn open(fs: &'a dyn FileSystem, path: &Path, format: Archive) -> io::Result<Self> {
+		let created = !fs.exists(path);
+		let file = fs.edit(path)?;
+
+		let mut writer = Self {
+			fs,
+			path: path.to_path_buf(),
+			file,
+			created,
+			end: 0,
+			tail: None,
+			format,
+			names: HashSet::new(),
+			central: Vec::new(),
+			entries: 0,
+		};
+
+		let end = match format {
+			Archive::Zip => writer.read_zip(),
+			Archive::Tar => writer.read_tar(),
+		}
+		.map_err(|err| invalid(format!("{}: {err}", path.display())))?;
+
+		let mut tail = Vec::new();
+		writer.file.seek(SeekFrom::Start(end))?;
+		writer.file.read_to_end(&mut tail)?;
+		writer.file.seek(SeekFrom::Start(end))?;
+		writer.end = end;
+		writer.tail = Some(tail);
+		Ok(writer)
+	}
+
+	fn read_tar(&mut self) -> io::Result<u64> {
+		let len = self.file.seek(SeekFrom::End(0))?;
+		let mut header = [0; BLOCK as usize];
+		let mut offset = 0;
+		let mut long_name = None;
+
+		while offset + BLOCK <= len {
+			self.file.seek(SeekFrom::Start(offset))?;
+			self.file.read_exact(&mut header)?;
+
+			if header.iter().all(|&byte| byte == 0) {
+				break;
+			}
+
+			let size = octal(&header[124..136])
+				.ok_or_else(|| invalid("Invalid tar header".to_string()))?;
+
+			match header[156] {
+				b'L' => {
+					let mut name = vec![0; size as usize];
+					self.file.read_exact(&mut name)?;
+					long_name = Some(text(&name));
+				}
+				0 | b'0' => {
+					let name = text(&header[..100]);
+					let name = match text(&header[345..500]) {
+						prefix if prefix.is_empty() => name,
+						prefix => format!("{prefix}/{name}"),
+					};
+					self.names.insert(long_name.take().unwrap_or(name));
+				}
+				_ => long_name = None,
+			}
+
+			offset += BLOCK + size.div_ceil(BLOCK) * BLOCK;
+		}
+
+		match offset > len {
+			true => Err(invalid("Truncated tar archive".to_string())),
+			false => Ok(offset),
+		}
+	}
+
+	fn read_zip(&mut self) -> io::Result<u64> {
+		let len = self.file.seek(SeekFrom::End(0))?;
+
+		if len == 0 {
+			return Ok(0);
+		}
+
+		let tail_len = len.min((END_OF_CENTRAL_DIRECTORY_LEN + u16::MAX as usize) as u64);
+		let mut tail = vec![0; tail_len as usize];
+		self.file.seek(SeekFrom::Start(len - tail_len))?;
+		self.file.read_exact(&mut tail)?;
+
+		let end = (0..=tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN))
+			.rev()
+			.find(|&idx| u32_at(&tail, idx).is_ok_and(|sig| sig == END_OF_CENTRAL_DIRECTORY))
+			.ok_or_else(|| invalid("Missing zip end of central directory".to_string()))?;
+		let record = &tail[end..];
+		let entries = u16_at(record, 10)?;
+		let size = u32_at(record, 12)?;
+		let offset = u64::from(u32_at(record, 16)?);
+
+		let mut central = vec![0; size as usize];
+		self.file.seek(SeekFrom::Start(offset))?;
+		self.file.read_exact(&mut central)?;
+
+		let mut idx = 0;
+
+		for _ in 0..entries {
+			if u32_at(&central, idx)? != CENTRAL_HEADER {
+				return Err(invalid("Invalid zip central directory".to_string()));
+			}
+
+			let name_len = usize::from(u16_at(&central, idx + 28)?);
+			let extra_len = usize::from(u16_at(&central, idx + 30)?);
+			let comment_len = usize::from(u16_at(&central, idx + 32)?);
+			let name = central
+				.get(idx + 46..idx + 46 + name_len)
+				.ok_or_else(|| invalid("Truncated zip archive".to_string()))?;
+
+			self.names
+				.insert(String::from_utf8_lossy(name).into_owned());
+			idx += 46 + name_len + extra_len + comment_len;
+		}
+
+		self.central = central;
+		self.entries = entries;
+		Ok(offset)
+	}
+
+	pub fn contains(&self, name: &str) -> bool {
+		self.names.contains(name)
+	}
+
+	pub fn append(&mut self, name: &str, reader: &mut dyn Read, modified: i64) -> io::Result<()> {
+		let start = self.file.stream_position()?;
+		let appended = match self.format {
+			Archive::Zip => self.append_zip(start, name, reader, modified),
+			Archive::Tar => self.append_tar(start, name, reader, modified),
+		};
+
+		match appended {
+			Ok(_) => {
+				self.names.insert(name.to_string());
+				Ok(())
+			}
+			Err(err) => {
+				self.file.seek(SeekFrom::Start(start))?;
+				Err(err)
+			}
+		}
+	}
+
+	fn append_tar(
+		&mut self,
+		start: u64,
+		name: &str,
+		reader: &mut dyn Read,
+		modified: i64,
+	) -> io::Result<()> {
+		let start = match split_name(name) {
+			Some(_) => start,
+			None => {
+				let long_name = [name.as_bytes(), &[0]].concat();
+				let size = long_name.len() as u64;
+				self.file
+					.write_all(&tar_header(LONG_NAME, size, 0, b'L')?)?;
+				self.file.write_all(&long_name)?;
+				self.file.write_all(&vec![0; padding(size)])?;
+				self.file.stream_position()?
+			}
+		};
+
+		self.file.write_all(&tar_header(name, 0, modified, b'0')?)?;
+
+		let (size, _) = stream(reader, &mut *self.file)?;
+		self.file.write_all(&vec![0; padding(size)])?;
+
+		let end = self.file.stream_position()?;
+		self.file.seek(SeekFrom::Start(start))?;
+		self.file
+			.write_all(&tar_header(name, size, modified, b'0')?)?;
+		self.file.seek(SeekFrom::Start(end))?;
+		Ok(())
+	}
+
+	fn append_zip(
+		&mut self,
+		start: u64,
+		name: &str,
+		reader: &mut dyn Read,
+		modified: i64,
+	) -> io::Result<()> {
+		let (offset, entries) = match (u32::try_from(start), self.entries.checked_add(1)) {
+			(Ok(offset), Some(entries)) => (offset, entries),
+			_ => return Err(invalid("Zip archive is too large".to_string())),
+		};
+		let name_len = u16::try_from(name.len())
+			.map_err(|_| invalid(format!("Name {name} is too long for zip")))?;
+
+		self.file.write_all(&vec![0; 30 + name.len()])?;
+
+		let (size, crc) = stream(reader, &mut *self.file)?;
+		let size = u32::try_from(size)
+			.map_err(|_| invalid(format!("File {name} is too large for zip")))?;
+		let (time, date) = dos_time(modified);
+
+		let mut local = Vec::with_capacity(30 + name.len());
+		local.extend(LOCAL_HEADER.to_le_bytes());
+		local.extend(ZIP_VERSION.to_le_bytes());
+		local.extend(ZIP_UTF8.to_le_bytes());
+		local.extend(0u16.to_le_bytes());
+		local.extend(time.to_le_bytes());
+		local.extend(date.to_le_bytes());
+		local.extend(crc.to_le_bytes());
+		local.extend(size.to_le_bytes());
+		local.extend(size.to_le_bytes());
+		local.extend(name_len.to_le_bytes());
+		local.extend(0u16.to_le_bytes());
+		local.extend(name.as_bytes());
+
+		let end = self.file.stream_position()?;
+		self.file.seek(SeekFrom::Start(start))?;
+		self.file.write_all(&local)?;
+		self.file.seek(SeekFrom::Start(end))?;
+
+		self.central.extend(CENTRAL_HEADER.to_le_bytes());
+		self.central.extend(ZIP_MADE_BY_UNIX.to_le_bytes());
+		self.central.extend(ZIP_VERSION.to_le_bytes());
+		self.central.extend(ZIP_UTF8.to_le_bytes());
+		self.central.extend(0u16.to_le_bytes());
+		self.central.extend(time.to_le_bytes());
+		self.central.extend(date.to_le_bytes());
+		self.central.extend(crc.to_le_bytes());
+		self.central.extend(size.to_le_bytes());
+		self.central.extend(size.to_le_bytes());
+		self.central.extend(name_len.to_le_bytes());
+		self.central.extend([0; 8]);
+		self.central.extend((FILE_MODE << 16).to_le_bytes());
+		self.central.extend(offset.to_le_bytes());
+		self.central.extend(name.as_bytes());
+		self.entries = entries;
+
+		Ok(())
+	}
+
+	pub fn finish(mut self) -> io::Result<()> {
+		match self.format {
+			Archive::Zip => {
+				let offset = u32::try_from(self.file.stream_position()?)
+					.map_err(|_| invalid("Zip archive is too large".to_string()))?;
+				let size = u32::try_from(self.central.len())
+					.map_err(|_| invalid("Zip archive is too large".to_string()))?;
+
+				let mut end = Vec::with_capacity(END_OF_CENTRAL_DIRECTORY_LEN);
+				end.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+				end.extend([0; 4]);
+				end.extend(self.entries.to_le_bytes());
+				end.extend(self.entries.to_le_bytes());
+				end.extend(size.to_le_bytes());
+				end.extend(offset.to_le_bytes());
+				end.extend(0u16.to_le_bytes());
+
+				self.file.write_all(&self.central)?;
+				self.file.write_all(&end)?;
+			}
+			Archive::Tar => self.file.write_all(&[0; 2 * BLOCK as usize])?,
+		}
+
+		let len = self.file.stream_position()?;
+		self.file.set_len(len)?;
+		self.file.sync_all()?;
+		self.tail = None;
+		Ok(())
+	}
+
+	fn restore(&mut self, tail: &[u8]) -> io::Result<()> {
+		self.file.seek(SeekFrom::Start(self.end))?;
+		self.file.write_all(tail)?;
+		self.file.set_len(self.end + tail.len() as u64)?;
+		self.file.sync_all()
+	}
+}
+
+impl Drop for Writer<'_> {
+	fn drop(&mut self) {
+		let Some(tail) = self.tail.take() else {
+			return;
+		};
+
+		match self.created {
+			true => {
+				let _ = self.fs.remove_file(&self.path);
+			}
+			false => {
+				let _ = self.restore(&tail);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fs::MemoryFileSystem;
+
+	const MODIFIED: i64 = 1_700_000_000;
+
+	fn write(fs: &dyn FileSystem, path: &Path, format: Archive, entries: &[(&str, &[u8])]) {
+		let mut writer = Writer::open(fs, path, format).unwrap();
+
+		for (name, contents) in entries {
+			writer.append(name, &mut &contents[..], MODIFIED).unwrap();
+		}
+
+		writer.finish().unwrap();
+	}
+
+	fn zip_entries(zip: &[u8]) -> Vec<(String, u32, Vec<u8>)> {
+		let end = &zip[zip.len() - END_OF_CENTRAL_DIRECTORY_LEN..];
+		assert_eq!(u32_at(end, 0).unwrap(), END_OF_CENTRAL_DIRECTORY);
+
+		let count = u16_at(end, 10).unwrap();
+		let mut idx = u32_at(end, 16).unwrap() as usize;
+		let mut entries = Vec::new();
+
+		for _ in 0..count {
+			assert_eq!(u32_at(zip, idx).unwrap(), CENTRAL_HEADER);
+
+			let crc = u32_at(zip, idx + 16).unwrap();
+			let size = u32_at(zip, idx + 20).unwrap() as usize;
+			let name_len = usize::from(u16_at(zip, idx + 28).unwrap());
+			let local = u32_at(zip, idx + 42).unwrap() as usize;
+			let name = String::from_utf8(zip[idx + 46..idx + 46 + name_len].to_vec()).unwrap();
+
+			assert_eq!(u32_at(zip, local).unwrap(), LOCAL_HEADER);
+			assert_eq!(u32_at(zip, local + 14).unwrap(), crc);
+			assert_eq!(&zip[local + 30..local + 30 + name_len], name.as_bytes());
+
+			let data = local + 30 + name_len;
+			entries.push((name, crc, zip[data..data + size].to_vec()));
+			idx += 46 + name_len;
+		}
+
+		entries
+	}
+
+	#[test]
+	fn zip_appends_to_existing_archive() {
+		let fs = MemoryFileSystem::new();
+		let path = Path::new("/2023.zip");
+		write(
+			&fs,
+			path,
+			Archive::Zip,
+			&[("a.jpg", b"alpha"), ("b.jpg", b"beta")],
+		);
+
+		let mut writer = Writer::open(&fs, path, Archive::Zip).unwrap();
+		assert!(writer.contains("a.jpg"));
+		assert!(writer.contains("b.jpg"));
+		assert!(!writer.contains("c.jpg"));
+
+		writer
+			.append("c.jpg", &mut &b"gamma"[..], MODIFIED)
+			.unwrap();
+		writer.finish().unwrap();
+
+		let entries = zip_entries(&fs.contents(path).unwrap());
+		let expected = [
+			("a.jpg", 0xd0e0396a, &b"alpha"[..]),
+			("b.jpg", 0x8f910463, &b"beta"[..]),
+			("c.jpg", 0xc443d071, &b"gamma"[..]),
+		];
+
+		assert_eq!(entries.len(), expected.len());
+
+		for ((name, crc, data), (expected_name, expected_crc, expected_data)) in
+			entries.iter().zip(expected)
+		{
+			assert_eq!(name, expected_name);
+			assert_eq!(*crc, expected_crc);
+			assert_eq!(data, expected_data);
+		}
+	}
+
+	#[test]
+	fn tar_appends_long_names_before_end_blocks() {
+		let fs = MemoryFileSystem::new();
+		let path = Path::new("/2023.tar");
+		let long_name = "n".repeat(120);
+		write(&fs, path, Archive::Tar, &[("a.jpg", b"alpha")]);
+		write(&fs, path, Archive::Tar, &[(&long_name, b"beta")]);
+
+		let tar = fs.contents(path).unwrap();
+		let header = |offset: usize| &tar[offset..offset + BLOCK as usize];
+
+		assert_eq!(text(&header(0)[..100]), "a.jpg");
+		assert_eq!(text(&header(1024)[..100]), LONG_NAME);
+		assert_eq!(text(&tar[1536..1536 + 121]), long_name);
+		assert_eq!(octal(&header(2048)[124..136]), Some(4));
+		assert_eq!(&tar[2560..2564], b"beta");
+		assert_eq!(tar.len(), 3072 + 2 * BLOCK as usize);
+		assert!(tar[3072..].iter().all(|&byte| byte == 0));
+
+		let writer = Writer::open(&fs, path, Archive::Tar).unwrap();
+		assert!(writer.contains("a.jpg"));
+		assert!(writer.contains(&long_name));
+	}
+
+	#[test]
+	fn unfinished_append_restores_archive() {
+		let fs = MemoryFileSystem::new();
+		let path = Path::new("/2023.zip");
+		write(&fs, path, Archive::Zip, &[("a.jpg", b"alpha")]);
+		let before = fs.contents(path).unwrap();
+
+		let mut writer = Writer::open(&fs, path, Archive::Zip).unwrap();
+		writer.append("b.jpg", &mut &b"beta"[..], MODIFIED).unwrap();
+		drop(writer);
+
+		assert_eq!(fs.contents(path).unwrap(), before);
+
+		drop(Writer::open(&fs, Path::new("/2024.zip"), Archive::Zip).unwrap());
+		assert!(!fs.exists(Path::new("/2024.zip")));
+	}
+}
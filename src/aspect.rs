@@ -0,0 +1,78 @@
+use crate::exif::Exif;
+use crate::fs::FileSystem;
+use crate::fs::Reader;
+use std::cmp::Ordering;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+fn jpeg(reader: &mut BufReader<Box<dyn Reader>>) -> Option<(u32, u32)> {
+	loop {
+		let mut header = [0; 4];
+		reader.read_exact(&mut header).ok()?;
+
+		if header[0] != 0xFF || matches!(header[1], 0xD9 | 0xDA) {
+			return None;
+		}
+
+		let len = u16::from_be_bytes([header[2], header[3]]).checked_sub(2)? as usize;
+
+		if matches!(header[1], 0xC0..=0xCF) && !matches!(header[1], 0xC4 | 0xC8 | 0xCC) {
+			let mut frame = [0; 5];
+			reader.read_exact(&mut frame).ok()?;
+			let height = u16::from_be_bytes([frame[1], frame[2]]);
+			let width = u16::from_be_bytes([frame[3], frame[4]]);
+			return Some((width.into(), height.into()));
+		}
+
+		reader.seek(SeekFrom::Current(len as i64)).ok()?;
+	}
+}
+
+fn dimensions(fs: &dyn FileSystem, path: &Path) -> Option<(u32, u32)> {
+	let mut reader = BufReader::new(fs.open(path).ok()?);
+	let mut head = [0; 24];
+	reader.read_exact(&mut head[..4]).ok()?;
+
+	match head {
+		[0xFF, 0xD8, ..] => {
+			reader.seek(SeekFrom::Start(2)).ok()?;
+			jpeg(&mut reader)
+		}
+		[0x89, b'P', b'N', b'G', ..] => {
+			reader.read_exact(&mut head[4..]).ok()?;
+			let width = u32::from_be_bytes(head[16..20].try_into().ok()?);
+			let height = u32::from_be_bytes(head[20..24].try_into().ok()?);
+			Some((width, height))
+		}
+		[b'G', b'I', b'F', b'8', ..] => {
+			reader.read_exact(&mut head[4..10]).ok()?;
+			let width = u16::from_le_bytes([head[6], head[7]]);
+			let height = u16::from_le_bytes([head[8], head[9]]);
+			Some((width.into(), height.into()))
+		}
+		_ => None,
+	}
+}
+
+pub fn aspect(fs: &dyn FileSystem, path: &Path) -> Option<&'static str> {
+	let exif = Exif::read(fs, path);
+	let (width, height) = exif
+		.as_ref()
+		.and_then(Exif::dimensions)
+		.or_else(|| dimensions(fs, path))
+		.filter(|(width, height)| *width > 0 && *height > 0)?;
+
+	let (width, height) = match exif.and_then(|exif| exif.orientation()) {
+		Some(5..=8) => (height, width),
+		_ => (width, height),
+	};
+
+	match width.cmp(&height) {
+		Ordering::Less => Some("portrait"),
+		Ordering::Greater => Some("landscape"),
+		Ordering::Equal => Some("square"),
+	}
+}
@@ -0,0 +1,107 @@
+use crate::fs::FileSystem;
+use crate::hash;
+use crate::hash::Hash;
+use crate::json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+struct Checksum {
+	size: u64,
+	modified: i64,
+	hash: Hash,
+}
+
+#[derive(Default)]
+pub struct ChecksumDb {
+	checksums: Mutex<HashMap<PathBuf, Checksum>>,
+}
+
+fn to_hex(hash: &Hash) -> String {
+	hash.iter().fold(String::new(), |mut hex, byte| {
+		let _ = write!(hex, "{byte:02x}");
+		hex
+	})
+}
+
+fn from_hex(hex: &str) -> Option<Hash> {
+	let bytes: Vec<_> = (0..hex.len())
+		.step_by(2)
+		.map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok())
+		.collect::<Option<_>>()?;
+
+	bytes.try_into().ok()
+}
+
+fn parse_entry(entry: &Value) -> Option<(PathBuf, Checksum)> {
+	let path = entry.get("path")?.as_path()?;
+	let size = entry.get("size")?.as_u64()?;
+	let modified = entry.get("modified")?.as_i64()?;
+	let hash = from_hex(entry.get("hash")?.as_str()?)?;
+
+	Some((path, Checksum { size, modified, hash }))
+}
+
+impl ChecksumDb {
+	pub fn load(path: &Path, fs: &dyn FileSystem) -> io::Result<Self> {
+		let text = match fs.read_to_string(path) {
+			Ok(text) => text,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+			Err(err) => return Err(err),
+		};
+
+		let checksums = match Value::parse(&text) {
+			Some(Value::Array(entries)) => entries.iter().filter_map(parse_entry).collect(),
+			_ => {
+				let message = format!("Invalid checksum database {}", path.display());
+				return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+			}
+		};
+
+		Ok(Self { checksums: Mutex::new(checksums) })
+	}
+
+	pub fn hash(
+		&self,
+		fs: &dyn FileSystem,
+		path: &Path,
+		progress: impl FnMut(u64),
+	) -> io::Result<Hash> {
+		let metadata = fs.metadata(path)?;
+
+		if let Some(checksum) = self.checksums.lock().unwrap().get(path) {
+			if checksum.size == metadata.len && checksum.modified == metadata.modified {
+				return Ok(checksum.hash);
+			}
+		}
+
+		let hash = hash::hash_reader_with(fs.open(path)?, progress)?;
+		let checksum = Checksum { size: metadata.len, modified: metadata.modified, hash };
+		self.checksums
+			.lock()
+			.unwrap()
+			.insert(path.to_path_buf(), checksum);
+
+		Ok(hash)
+	}
+
+	pub fn save(&self, path: &Path, fs: &dyn FileSystem) -> io::Result<()> {
+		let checksums = self.checksums.lock().unwrap();
+		let entries = checksums
+			.iter()
+			.map(|(path, checksum)| {
+				Value::object([
+					("path", Value::from(path.as_path())),
+					("size", Value::from(checksum.size)),
+					("modified", Value::from(checksum.modified)),
+					("hash", Value::from(to_hex(&checksum.hash))),
+				])
+			})
+			.collect();
+
+		fs.write(path, format!("{}\n", Value::Array(entries)).as_bytes())
+	}
+}
@@ -0,0 +1,198 @@
+use clap::Arg;
+use clap::Command;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+	Bash,
+	Zsh,
+	Fish,
+	Powershell,
+}
+
+struct Opt {
+	long: Option<String>,
+	short: Option<char>,
+	takes_value: bool,
+	values: Vec<String>,
+	value_name: String,
+}
+
+impl From<&Arg> for Opt {
+	fn from(arg: &Arg) -> Self {
+		let values = arg
+			.get_possible_values()
+			.iter()
+			.filter(|value| !value.is_hide_set())
+			.map(|value| value.get_name().to_string())
+			.collect();
+		let value_name = arg
+			.get_value_names()
+			.and_then(|names| names.first())
+			.map_or(arg.get_id().to_string(), |name| name.to_string());
+
+		Self {
+			long: arg.get_long().map(str::to_string),
+			short: arg.get_short(),
+			takes_value: arg.get_action().takes_values(),
+			values,
+			value_name,
+		}
+	}
+}
+
+impl Opt {
+	fn flags(&self) -> Vec<String> {
+		let long = self.long.iter().map(|long| format!("--{long}"));
+		let short = self.short.iter().map(|short| format!("-{short}"));
+		long.chain(short).collect()
+	}
+}
+
+fn options(command: &Command) -> Vec<Opt> {
+	command
+		.get_arguments()
+		.filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+		.map(Opt::from)
+		.collect()
+}
+
+fn subcommands(command: &Command) -> Vec<String> {
+	command
+		.get_subcommands()
+		.filter(|subcommand| !subcommand.is_hide_set())
+		.map(|subcommand| subcommand.get_name().to_string())
+		.collect()
+}
+
+fn words(options: &[Opt], subcommands: &[String]) -> Vec<String> {
+	options
+		.iter()
+		.flat_map(Opt::flags)
+		.chain(subcommands.iter().cloned())
+		.collect()
+}
+
+fn bash(name: &str, options: &[Opt], subcommands: &[String]) -> Vec<String> {
+	let mut lines = vec![
+		format!("_{name}() {{"),
+		"\tlocal cur=\"${COMP_WORDS[COMP_CWORD]}\"".to_string(),
+		"\tlocal prev=\"${COMP_WORDS[COMP_CWORD-1]}\"".to_string(),
+		"\tcase \"$prev\" in".to_string(),
+	];
+
+	for opt in options.iter().filter(|opt| opt.takes_value) {
+		let reply = match opt.values.is_empty() {
+			true => "compgen -f -- \"$cur\"".to_string(),
+			false => format!("compgen -W \"{}\" -- \"$cur\"", opt.values.join(" ")),
+		};
+
+		lines.push(format!("\t\t{})", opt.flags().join("|")));
+		lines.push(format!("\t\t\tCOMPREPLY=($({reply}))"));
+		lines.push("\t\t\treturn".to_string());
+		lines.push("\t\t\t;;".to_string());
+	}
+
+	let words = words(options, subcommands).join(" ");
+
+	lines.push("\tesac".to_string());
+	lines.push(format!(
+		"\tCOMPREPLY=($(compgen -W \"{words}\" -- \"$cur\"))"
+	));
+	lines.push("}".to_string());
+	lines.push(format!("complete -F _{name} -o default {name}"));
+	lines
+}
+
+fn zsh(name: &str, options: &[Opt], subcommands: &[String]) -> Vec<String> {
+	let mut lines = vec![
+		format!("#compdef {name}"),
+		String::new(),
+		"_arguments -s \\".to_string(),
+	];
+
+	for opt in options {
+		let action = match (opt.takes_value, opt.values.is_empty()) {
+			(false, _) => String::new(),
+			(true, true) => format!(":{}:_files", opt.value_name),
+			(true, false) => format!(":{}:({})", opt.value_name, opt.values.join(" ")),
+		};
+
+		for flag in opt.flags() {
+			let separator = match (opt.takes_value, flag.starts_with("--")) {
+				(false, _) => "",
+				(true, true) => "=",
+				(true, false) => "+",
+			};
+
+			lines.push(format!("\t'{flag}{separator}{action}' \\"));
+		}
+	}
+
+	if !subcommands.is_empty() {
+		lines.push(format!("\t'1:command:({})' \\", subcommands.join(" ")));
+	}
+
+	lines.push("\t'*:file:_files'".to_string());
+	lines
+}
+
+fn fish(name: &str, options: &[Opt], subcommands: &[String]) -> Vec<String> {
+	let mut lines: Vec<_> = subcommands
+		.iter()
+		.map(|subcommand| format!("complete -c {name} -n __fish_use_subcommand -f -a {subcommand}"))
+		.collect();
+
+	for opt in options {
+		let mut line = format!("complete -c {name}");
+
+		if let Some(long) = &opt.long {
+			line.push_str(&format!(" -l {long}"));
+		}
+
+		if let Some(short) = opt.short {
+			line.push_str(&format!(" -s {short}"));
+		}
+
+		match (opt.takes_value, opt.values.is_empty()) {
+			(false, _) => {}
+			(true, true) => line.push_str(" -r -F"),
+			(true, false) => line.push_str(&format!(" -x -a \"{}\"", opt.values.join(" "))),
+		}
+
+		lines.push(line);
+	}
+
+	lines
+}
+
+fn powershell(name: &str, options: &[Opt], subcommands: &[String]) -> Vec<String> {
+	let words: Vec<_> = words(options, subcommands)
+		.iter()
+		.map(|word| format!("'{word}'"))
+		.collect();
+
+	vec![
+		format!("Register-ArgumentCompleter -Native -CommandName '{name}' -ScriptBlock {{"),
+		"\tparam($wordToComplete, $commandAst, $cursorPosition)".to_string(),
+		format!("\t@({}) |", words.join(", ")),
+		"\t\tWhere-Object { $_ -like \"$wordToComplete*\" } |".to_string(),
+		"\t\tForEach-Object { [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterName', $_) }".to_string(),
+		"}".to_string(),
+	]
+}
+
+pub fn generate(shell: Shell, command: &Command) -> String {
+	let name = command.get_name();
+	let options = options(command);
+	let subcommands = subcommands(command);
+
+	let lines = match shell {
+		Shell::Bash => bash(name, &options, &subcommands),
+		Shell::Zsh => zsh(name, &options, &subcommands),
+		Shell::Fish => fish(name, &options, &subcommands),
+		Shell::Powershell => powershell(name, &options, &subcommands),
+	};
+
+	lines.join("\n") + "\n"
+}
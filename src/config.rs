@@ -0,0 +1,131 @@
+use clap::Command;
+use std::path::Path;
+use tf::Extension;
+
+#[derive(Default)]
+pub struct ConfigFile {
+	destination: Option<String>,
+	person: Option<String>,
+	mode: Option<String>,
+	template: Option<String>,
+	map: Vec<(String, Extension)>,
+}
+
+fn parse_string(value: &str) -> Result<(String, &str), String> {
+	let mut chars = value.char_indices();
+	let quote = match chars.next() {
+		Some((_, quote @ ('"' | '\''))) => quote,
+		_ => return Err(format!("expected a quoted string, got {value}")),
+	};
+
+	let mut text = String::new();
+
+	while let Some((idx, c)) = chars.next() {
+		match c {
+			c if c == quote => return Ok((text, &value[idx + 1..])),
+			'\\' if quote == '"' => match chars.next() {
+				Some((_, 'n')) => text.push('\n'),
+				Some((_, 't')) => text.push('\t'),
+				Some((_, c @ ('"' | '\\'))) => text.push(c),
+				_ => return Err("invalid escape sequence".to_string()),
+			},
+			c => text.push(c),
+		}
+	}
+
+	Err("unterminated string".to_string())
+}
+
+fn parse_line(line: &str) -> Result<(String, String), String> {
+	let (key, value) = line
+		.split_once('=')
+		.ok_or(format!("expected KEY = VALUE, got {line}"))?;
+	let key = key.trim();
+	let key = match key.starts_with(['"', '\'']) {
+		true => parse_string(key)?.0,
+		false => key.to_string(),
+	};
+
+	let (value, rest) = parse_string(value.trim())?;
+	let rest = rest.trim();
+
+	if !rest.is_empty() && !rest.starts_with('#') {
+		return Err(format!("unexpected {rest} after value"));
+	}
+
+	Ok((key, value))
+}
+
+impl ConfigFile {
+	pub fn read(path: &Path) -> Result<Self, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		let mut config = ConfigFile::default();
+		let mut table = None;
+
+		for (idx, line) in text.lines().enumerate() {
+			let line = line.trim();
+
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let line_error = |err: String| format!("line {}: {err}", idx + 1);
+
+			if let Some(name) = line
+				.strip_prefix('[')
+				.and_then(|line| line.strip_suffix(']'))
+			{
+				match name.trim() {
+					"map" => table = Some("map"),
+					name => return Err(line_error(format!("unknown table [{name}]"))),
+				}
+
+				continue;
+			}
+
+			let (key, value) = parse_line(line).map_err(line_error)?;
+
+			match (table, key.as_str()) {
+				(Some("map"), _) => config
+					.map
+					.push(tf::parse_map(&format!("{key}={value}")).map_err(line_error)?),
+				(_, "destination") => config.destination = Some(value),
+				(_, "person") => config.person = Some(value),
+				(_, "mode") if matches!(value.as_str(), "move" | "copy" | "hardlink") => {
+					config.mode = Some(value)
+				}
+				(_, "mode") => {
+					return Err(line_error(format!(
+						"mode must be move, copy or hardlink, got {value}"
+					)))
+				}
+				(_, "template") => config.template = Some(value),
+				(_, key) => return Err(line_error(format!("unknown key {key}"))),
+			}
+		}
+
+		Ok(config)
+	}
+
+	pub fn map(&self) -> &[(String, Extension)] {
+		&self.map
+	}
+
+	pub fn apply(self, mut command: Command) -> Command {
+		let defaults = [
+			("destination", self.destination),
+			("person", self.person),
+			("mode", self.mode),
+			("template", self.template),
+		];
+
+		for (id, value) in defaults {
+			if let Some(value) = value {
+				let value: &'static str = value.leak();
+				command = command.mut_arg(id, |arg| arg.default_value(value).required(false));
+			}
+		}
+
+		command
+	}
+}
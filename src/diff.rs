@@ -0,0 +1,64 @@
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+	New,
+	Collision,
+	Identical,
+}
+
+impl Display for Change {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Change::New => write!(f, "new"),
+			Change::Collision => write!(f, "collision"),
+			Change::Identical => write!(f, "identical"),
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct Diff {
+	rows: Vec<(Change, PathBuf, PathBuf)>,
+}
+
+impl Diff {
+	pub fn add(&mut self, change: Change, source: PathBuf, destination: PathBuf) {
+		self.rows.push((change, source, destination));
+	}
+
+	fn count(&self, change: Change) -> usize {
+		self.rows
+			.iter()
+			.filter(|(row, _, _)| *row == change)
+			.count()
+	}
+}
+
+impl Display for Diff {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let mut rows: Vec<_> = self.rows.iter().collect();
+		rows.sort();
+
+		for (change, source, destination) in rows {
+			writeln!(
+				f,
+				"{:<9}  {} -> {}",
+				change.to_string(),
+				source.display(),
+				destination.display()
+			)?;
+		}
+
+		writeln!(
+			f,
+			"new: {}, collision: {}, identical: {}",
+			self.count(Change::New),
+			self.count(Change::Collision),
+			self.count(Change::Identical)
+		)
+	}
+}
@@ -0,0 +1,113 @@
+use crate::fs::FileSystem;
+use crate::fs::Reader;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+pub fn find_box(
+	file: &mut dyn Reader,
+	mut offset: u64,
+	end: u64,
+	name: &[u8; 4],
+) -> io::Result<Option<(u64, u64)>> {
+	while offset.checked_add(8).is_some_and(|header| header <= end) {
+		let mut header = [0; 8];
+		file.seek(SeekFrom::Start(offset))?;
+		file.read_exact(&mut header)?;
+
+		let (body, size) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+			0 => (offset + 8, end - offset),
+			1 => {
+				let mut size = [0; 8];
+				file.read_exact(&mut size)?;
+				(offset + 16, u64::from_be_bytes(size))
+			}
+			size => (offset + 8, u64::from(size)),
+		};
+
+		let next = match offset.checked_add(size) {
+			Some(next) if size >= 8 && size >= body - offset => next,
+			_ => return Ok(None),
+		};
+
+		if &header[4..] == name {
+			return Ok(Some((body, next)));
+		}
+
+		offset = next;
+	}
+
+	Ok(None)
+}
+
+fn mp4(fs: &dyn FileSystem, path: &Path) -> io::Result<Option<Duration>> {
+	let mut file = fs.open(path)?;
+	let len = fs.metadata(path)?.len;
+
+	let Some((start, end)) = find_box(&mut *file, 0, len, b"moov")? else {
+		return Ok(None);
+	};
+
+	let Some((start, _)) = find_box(&mut *file, start, end, b"mvhd")? else {
+		return Ok(None);
+	};
+
+	let mut header = [0; 32];
+	file.seek(SeekFrom::Start(start))?;
+	file.read_exact(&mut header)?;
+
+	let u32_at = |idx: usize| {
+		u32::from_be_bytes([
+			header[idx],
+			header[idx + 1],
+			header[idx + 2],
+			header[idx + 3],
+		])
+	};
+	let (timescale, duration) = match header[0] {
+		1 => (
+			u32_at(20),
+			u64::from(u32_at(24)) << 32 | u64::from(u32_at(28)),
+		),
+		_ => (u32_at(12), u64::from(u32_at(16))),
+	};
+
+	match timescale {
+		0 => Ok(None),
+		timescale => Ok(Duration::try_from_secs_f64(duration as f64 / f64::from(timescale)).ok()),
+	}
+}
+
+fn ffprobe(path: &Path) -> Option<Duration> {
+	let output = Command::new("ffprobe")
+		.args(["-v", "error", "-show_entries", "format=duration"])
+		.args(["-of", "default=noprint_wrappers=1:nokey=1"])
+		.arg(path)
+		.output()
+		.ok()?;
+
+	if !output.status.success() {
+		return None;
+	}
+
+	let seconds = String::from_utf8_lossy(&output.stdout)
+		.trim()
+		.parse()
+		.ok()?;
+	Duration::try_from_secs_f64(seconds).ok()
+}
+
+pub fn ffprobe_available() -> bool {
+	Command::new("ffprobe")
+		.arg("-version")
+		.output()
+		.is_ok_and(|output| output.status.success())
+}
+
+pub fn duration(fs: &dyn FileSystem, path: &Path) -> Option<Duration> {
+	mp4(fs, path).ok().flatten().or_else(|| ffprobe(path))
+}
@@ -0,0 +1,78 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Clone)]
+pub struct Exec(Vec<String>);
+
+impl Exec {
+	pub fn parse(command: &str) -> Result<Self, String> {
+		let mut args = Vec::new();
+		let mut arg = None::<String>;
+		let mut quote = None;
+		let mut chars = command.chars();
+
+		while let Some(c) = chars.next() {
+			match (quote, c) {
+				(Some(open), c) if c == open => quote = None,
+				(Some('"') | None, '\\') => {
+					let escaped = chars
+						.next()
+						.ok_or(format!("Trailing backslash in {command}"))?;
+					arg.get_or_insert_default().push(escaped);
+				}
+				(Some(_), c) => arg.get_or_insert_default().push(c),
+				(None, '\'' | '"') => {
+					quote = Some(c);
+					arg.get_or_insert_default();
+				}
+				(None, c) if c.is_whitespace() => args.extend(arg.take()),
+				(None, c) => arg.get_or_insert_default().push(c),
+			}
+		}
+
+		if quote.is_some() {
+			return Err(format!("Unclosed quote in {command}"));
+		}
+
+		args.extend(arg);
+
+		match args.is_empty() {
+			true => Err("Empty command".to_string()),
+			false => Ok(Self(args)),
+		}
+	}
+
+	pub fn run(&self, path: &Path) -> Result<(), String> {
+		let args: Vec<OsString> = self
+			.0
+			.iter()
+			.map(|arg| {
+				let mut expanded = OsString::new();
+				let mut parts = arg.split("{}");
+
+				if let Some(part) = parts.next() {
+					expanded.push(part);
+				}
+
+				for part in parts {
+					expanded.push(path);
+					expanded.push(part);
+				}
+
+				expanded
+			})
+			.collect();
+
+		let status = Command::new(&args[0])
+			.args(&args[1..])
+			.status()
+			.map_err(|err| format!("{} could not be run: {err}", self.0[0]))?;
+
+		match status.code() {
+			Some(0) => Ok(()),
+			Some(code) => Err(format!("{} exited with status {code}", self.0[0])),
+			None => Err(format!("{} was terminated by a signal", self.0[0])),
+		}
+	}
+}
@@ -0,0 +1,370 @@
+use crate::duration::find_box;
+use crate::fs::FileSystem;
+use crate::fs::Reader;
+use chrono::NaiveDateTime;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+const TIFF_LIMIT: u64 = 1 << 20;
+const BOX_LIMIT: u64 = 1 << 16;
+
+const MAKE: u16 = 0x010F;
+const MODEL: u16 = 0x0110;
+const ARTIST: u16 = 0x013B;
+const COPYRIGHT: u16 = 0x8298;
+const ORIENTATION: u16 = 0x0112;
+
+const EXIF_IFD: u16 = 0x8769;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const PIXEL_X_DIMENSION: u16 = 0xA002;
+const PIXEL_Y_DIMENSION: u16 = 0xA003;
+
+const GPS_IFD: u16 = 0x8825;
+const GPS_LATITUDE_REF: u16 = 1;
+const GPS_LATITUDE: u16 = 2;
+const GPS_LONGITUDE_REF: u16 = 3;
+const GPS_LONGITUDE: u16 = 4;
+
+const ASCII: u16 = 2;
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+struct Entry {
+	kind: u16,
+	count: usize,
+	value: usize,
+}
+
+fn be(bytes: &[u8], offset: usize, size: usize) -> Option<u64> {
+	let bytes = bytes.get(offset..offset.checked_add(size)?)?;
+	Some(
+		bytes
+			.iter()
+			.fold(0, |value, &byte| value << 8 | u64::from(byte)),
+	)
+}
+
+fn read_box(reader: &mut dyn Reader, (start, end): (u64, u64)) -> Option<Vec<u8>> {
+	let mut body = Vec::new();
+	reader.seek(SeekFrom::Start(start)).ok()?;
+	reader
+		.take(end.checked_sub(start)?.min(BOX_LIMIT))
+		.read_to_end(&mut body)
+		.ok()?;
+	Some(body)
+}
+
+fn exif_item(iinf: &[u8]) -> Option<u64> {
+	let (mut offset, count) = match iinf.first()? {
+		0 => (6, be(iinf, 4, 2)?),
+		_ => (8, be(iinf, 4, 4)?),
+	};
+
+	for _ in 0..count {
+		let size = usize::try_from(be(iinf, offset, 4)?).ok()?;
+		let next = offset.checked_add(size).filter(|_| size >= 8)?;
+		let infe = iinf.get(offset + 8..next)?;
+
+		let item = match (iinf.get(offset + 4..offset + 8)?, infe.first()?) {
+			(b"infe", 2) => Some((be(infe, 4, 2)?, infe.get(8..12)?)),
+			(b"infe", 3) => Some((be(infe, 4, 4)?, infe.get(10..14)?)),
+			_ => None,
+		};
+
+		if let Some((id, b"Exif")) = item {
+			return Some(id);
+		}
+
+		offset = next;
+	}
+
+	None
+}
+
+fn item_extents(iloc: &[u8], id: u64) -> Option<Vec<(u64, u64)>> {
+	let version = *iloc.first()?;
+	let offset_size = usize::from(iloc.get(4)? >> 4);
+	let length_size = usize::from(iloc.get(4)? & 0xF);
+	let base_size = usize::from(iloc.get(5)? >> 4);
+	let (id_size, index_size) = match version {
+		1 => (2, usize::from(iloc.get(5)? & 0xF)),
+		2 => (4, usize::from(iloc.get(5)? & 0xF)),
+		_ => (2, 0),
+	};
+
+	let count = be(iloc, 6, id_size)?;
+	let mut offset = 6 + id_size;
+
+	for _ in 0..count {
+		let item = be(iloc, offset, id_size)?;
+		offset += id_size;
+
+		let method = match version {
+			1 | 2 => be(iloc, offset, 2)? & 0xF,
+			_ => 0,
+		};
+		offset += match version {
+			1 | 2 => 4,
+			_ => 2,
+		};
+
+		let base = be(iloc, offset, base_size)?;
+		let extent_count = be(iloc, offset + base_size, 2)?;
+		offset += base_size + 2;
+
+		let mut extents = Vec::new();
+
+		for _ in 0..extent_count {
+			let start = be(iloc, offset + index_size, offset_size)?;
+			let length = be(iloc, offset + index_size + offset_size, length_size)?;
+			extents.push((base.checked_add(start)?, length));
+			offset += index_size + offset_size + length_size;
+		}
+
+		if item == id {
+			return (method == 0).then_some(extents);
+		}
+	}
+
+	None
+}
+
+pub struct Exif {
+	tiff: Vec<u8>,
+	little_endian: bool,
+}
+
+impl Exif {
+	pub fn read(fs: &dyn FileSystem, path: &Path) -> Option<Self> {
+		let mut reader = BufReader::new(fs.open(path).ok()?);
+		let mut magic = [0; 8];
+		reader.read_exact(&mut magic).ok()?;
+
+		let tiff = match magic {
+			[0xFF, 0xD8, ..] => {
+				reader.seek(SeekFrom::Start(2)).ok()?;
+				Self::jpeg_segment(&mut reader)?
+			}
+			[b'I', b'I', 42, 0, ..] | [b'M', b'M', 0, 42, ..] => {
+				let mut tiff = magic.to_vec();
+				reader.take(TIFF_LIMIT).read_to_end(&mut tiff).ok()?;
+				tiff
+			}
+			[_, _, _, _, b'f', b't', b'y', b'p'] => Self::heif_item(&mut reader)?,
+			_ => return None,
+		};
+
+		let little_endian = match tiff.get(..2)? {
+			b"II" => true,
+			b"MM" => false,
+			_ => return None,
+		};
+
+		Some(Self { tiff, little_endian })
+	}
+
+	fn heif_item(reader: &mut dyn Reader) -> Option<Vec<u8>> {
+		let len = reader.seek(SeekFrom::End(0)).ok()?;
+		let (start, end) = find_box(reader, 0, len, b"meta").ok()??;
+		let start = start + 4;
+
+		let iinf = find_box(reader, start, end, b"iinf").ok()??;
+		let iloc = find_box(reader, start, end, b"iloc").ok()??;
+		let id = exif_item(&read_box(reader, iinf)?)?;
+		let extents = item_extents(&read_box(reader, iloc)?, id)?;
+
+		let mut item = Vec::new();
+
+		for (offset, length) in extents {
+			let limit = TIFF_LIMIT.saturating_sub(item.len() as u64);
+			reader.seek(SeekFrom::Start(offset)).ok()?;
+			reader.take(length.min(limit)).read_to_end(&mut item).ok()?;
+		}
+
+		let skip = usize::try_from(be(&item, 0, 4)?).ok()?;
+		item.get(skip.checked_add(4)?..).map(<[u8]>::to_vec)
+	}
+
+	fn jpeg_segment(reader: &mut BufReader<Box<dyn Reader>>) -> Option<Vec<u8>> {
+		loop {
+			let mut header = [0; 4];
+			reader.read_exact(&mut header).ok()?;
+
+			if header[0] != 0xFF || matches!(header[1], 0xD9 | 0xDA) {
+				return None;
+			}
+
+			let len = u16::from_be_bytes([header[2], header[3]]).checked_sub(2)? as usize;
+
+			if header[1] != 0xE1 {
+				reader.seek(SeekFrom::Current(len as i64)).ok()?;
+				continue;
+			}
+
+			let mut segment = vec![0; len];
+			reader.read_exact(&mut segment).ok()?;
+
+			if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+				return Some(tiff.to_vec());
+			}
+		}
+	}
+
+	fn u16(&self, offset: usize) -> Option<u16> {
+		let bytes = self.tiff.get(offset..offset + 2)?.try_into().ok()?;
+
+		match self.little_endian {
+			true => Some(u16::from_le_bytes(bytes)),
+			false => Some(u16::from_be_bytes(bytes)),
+		}
+	}
+
+	fn u32(&self, offset: usize) -> Option<u32> {
+		let bytes = self.tiff.get(offset..offset + 4)?.try_into().ok()?;
+
+		match self.little_endian {
+			true => Some(u32::from_le_bytes(bytes)),
+			false => Some(u32::from_be_bytes(bytes)),
+		}
+	}
+
+	fn ifd0(&self) -> Option<usize> {
+		Some(self.u32(4)? as usize)
+	}
+
+	fn find(&self, ifd: usize, tag: u16) -> Option<Entry> {
+		let count = self.u16(ifd)? as usize;
+
+		(0..count).map(|idx| ifd + 2 + idx * 12).find_map(|entry| {
+			if self.u16(entry)? != tag {
+				return None;
+			}
+
+			Some(Entry {
+				kind: self.u16(entry + 2)?,
+				count: self.u32(entry + 4)? as usize,
+				value: entry + 8,
+			})
+		})
+	}
+
+	fn data(&self, entry: &Entry, size: usize) -> Option<usize> {
+		match entry.count * size {
+			len if len <= 4 => Some(entry.value),
+			_ => Some(self.u32(entry.value)? as usize),
+		}
+	}
+
+	fn sub_ifd(&self, tag: u16) -> Option<usize> {
+		let entry = self.find(self.ifd0()?, tag)?;
+
+		if entry.kind != LONG {
+			return None;
+		}
+
+		Some(self.u32(entry.value)? as usize)
+	}
+
+	fn ascii(&self, ifd: usize, tag: u16) -> Option<String> {
+		let entry = self.find(ifd, tag)?;
+
+		if entry.kind != ASCII {
+			return None;
+		}
+
+		let offset = self.data(&entry, 1)?;
+		let bytes = self.tiff.get(offset..offset + entry.count)?;
+		let text = bytes.split(|byte| *byte == 0).next()?;
+
+		Some(String::from_utf8_lossy(text).trim().to_string())
+	}
+
+	fn integer(&self, ifd: usize, tag: u16) -> Option<u32> {
+		let entry = self.find(ifd, tag)?;
+
+		match entry.kind {
+			SHORT => Some(self.u16(entry.value)?.into()),
+			LONG => self.u32(entry.value),
+			_ => None,
+		}
+	}
+
+	fn rationals(&self, ifd: usize, tag: u16) -> Option<Vec<f64>> {
+		let entry = self.find(ifd, tag)?;
+
+		if entry.kind != RATIONAL {
+			return None;
+		}
+
+		let offset = self.data(&entry, 8)?;
+
+		(0..entry.count)
+			.map(|idx| {
+				let numerator = self.u32(offset + idx * 8)? as f64;
+				let denominator = self.u32(offset + idx * 8 + 4)? as f64;
+				(denominator != 0.0).then(|| numerator / denominator)
+			})
+			.collect()
+	}
+
+	fn coordinate(&self, ifd: usize, tag: u16, reference: u16, negative: &str) -> Option<f64> {
+		let [degrees, minutes, seconds] = self.rationals(ifd, tag)?.try_into().ok()?;
+		let value = degrees + minutes / 60.0 + seconds / 3600.0;
+
+		match self.ascii(ifd, reference)?.as_str() {
+			reference if reference == negative => Some(-value),
+			_ => Some(value),
+		}
+	}
+
+	pub fn gps(&self) -> Option<(f64, f64)> {
+		let ifd = self.sub_ifd(GPS_IFD)?;
+		let latitude = self.coordinate(ifd, GPS_LATITUDE, GPS_LATITUDE_REF, "S")?;
+		let longitude = self.coordinate(ifd, GPS_LONGITUDE, GPS_LONGITUDE_REF, "W")?;
+
+		Some((latitude, longitude))
+	}
+
+	pub fn camera(&self) -> Option<String> {
+		let ifd = self.ifd0()?;
+		let make = self.ascii(ifd, MAKE).unwrap_or_default();
+		let model = self.ascii(ifd, MODEL).unwrap_or_default();
+
+		let camera = match model.to_lowercase().starts_with(&make.to_lowercase()) {
+			true => model,
+			false => format!("{make} {model}"),
+		};
+
+		Some(camera.trim().to_string()).filter(|camera| !camera.is_empty())
+	}
+
+	pub fn artist(&self) -> Option<String> {
+		let ifd = self.ifd0()?;
+
+		[ARTIST, COPYRIGHT]
+			.into_iter()
+			.filter_map(|tag| self.ascii(ifd, tag))
+			.find(|artist| !artist.is_empty())
+	}
+
+	pub fn orientation(&self) -> Option<u32> {
+		self.integer(self.ifd0()?, ORIENTATION)
+	}
+
+	pub fn dimensions(&self) -> Option<(u32, u32)> {
+		let ifd = self.sub_ifd(EXIF_IFD)?;
+		let width = self.integer(ifd, PIXEL_X_DIMENSION)?;
+		let height = self.integer(ifd, PIXEL_Y_DIMENSION)?;
+
+		Some((width, height))
+	}
+
+	pub fn date_time_original(&self) -> Option<NaiveDateTime> {
+		let text = self.ascii(self.sub_ifd(EXIF_IFD)?, DATE_TIME_ORIGINAL)?;
+		NaiveDateTime::parse_from_str(&text, "%Y:%m:%d %H:%M:%S").ok()
+	}
+}
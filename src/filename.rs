@@ -0,0 +1,53 @@
+use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use std::iter;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+const PATTERNS: &[&str] = &[
+	"%Y%m%d_%H%M%S",
+	"%Y%m%d-%H%M%S",
+	"%Y%m%d%H%M%S",
+	"%Y-%m-%d_%H-%M-%S",
+	"%Y-%m-%d-%H-%M-%S",
+	"%Y-%m-%d %H.%M.%S",
+	"%Y-%m-%d at %H.%M.%S",
+	"%Y%m%d",
+	"%Y-%m-%d",
+	"%Y_%m_%d",
+];
+
+const YEARS: RangeInclusive<i32> = 1970..=2100;
+
+fn parse(text: &str, pattern: &str) -> Option<NaiveDateTime> {
+	let (date, rest) = NaiveDateTime::parse_and_remainder(text, pattern)
+		.or_else(|_| {
+			NaiveDate::parse_and_remainder(text, pattern)
+				.map(|(date, rest)| (date.and_hms_opt(0, 0, 0).unwrap_or_default(), rest))
+		})
+		.ok()?;
+
+	match rest.starts_with(|c: char| c.is_ascii_digit()) || !YEARS.contains(&date.year()) {
+		true => None,
+		false => Some(date),
+	}
+}
+
+pub fn date(path: &Path, patterns: &[String]) -> Option<NaiveDateTime> {
+	let stem = path.file_name()?.to_string_lossy();
+	let starts = stem.char_indices().filter_map(|(idx, c)| {
+		let start = c.is_ascii_digit() && !stem[..idx].ends_with(|c: char| c.is_ascii_digit());
+		start.then_some(idx)
+	});
+
+	iter::once(0).chain(starts).find_map(|idx| {
+		let text = &stem[idx..];
+
+		patterns
+			.iter()
+			.map(String::as_str)
+			.chain(PATTERNS.iter().copied())
+			.find_map(|pattern| parse(text, pattern))
+	})
+}
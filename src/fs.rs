@@ -0,0 +1,670 @@
+use filetime::FileTime;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+#[cfg(unix)]
+use std::ffi::CString;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+#[cfg(unix)]
+use std::mem::MaybeUninit;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+#[derive(Clone, Copy)]
+pub struct Metadata {
+	pub len: u64,
+	pub modified: i64,
+	pub is_dir: bool,
+}
+
+impl From<&std::fs::Metadata> for Metadata {
+	fn from(metadata: &std::fs::Metadata) -> Self {
+		Self {
+			len: metadata.len(),
+			modified: FileTime::from_last_modification_time(metadata).seconds(),
+			is_dir: metadata.is_dir(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct DirEntry {
+	path: PathBuf,
+	depth: usize,
+	is_dir: bool,
+	is_symlink: bool,
+}
+
+impl DirEntry {
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub fn into_path(self) -> PathBuf {
+		self.path
+	}
+
+	pub fn file_name(&self) -> &OsStr {
+		self.path.file_name().unwrap_or(self.path.as_os_str())
+	}
+
+	pub fn depth(&self) -> usize {
+		self.depth
+	}
+
+	pub fn is_dir(&self) -> bool {
+		self.is_dir
+	}
+
+	pub fn path_is_symlink(&self) -> bool {
+		self.is_symlink
+	}
+}
+
+impl From<&walkdir::DirEntry> for DirEntry {
+	fn from(entry: &walkdir::DirEntry) -> Self {
+		Self {
+			path: entry.path().to_path_buf(),
+			depth: entry.depth(),
+			is_dir: entry.file_type().is_dir(),
+			is_symlink: entry.path_is_symlink(),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct WalkError {
+	pub path: Option<PathBuf>,
+	pub err: io::Error,
+}
+
+impl Display for WalkError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match &self.path {
+			Some(path) => write!(f, "{}: {}", path.display(), self.err),
+			None => write!(f, "{}", self.err),
+		}
+	}
+}
+
+impl std::error::Error for WalkError {}
+
+impl From<walkdir::Error> for WalkError {
+	fn from(err: walkdir::Error) -> Self {
+		Self {
+			path: err.path().map(Path::to_path_buf),
+			err: io::Error::from(err),
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct Walk {
+	pub follow_links: bool,
+	pub min_depth: usize,
+	pub max_depth: usize,
+}
+
+impl Default for Walk {
+	fn default() -> Self {
+		Self { follow_links: false, min_depth: 0, max_depth: usize::MAX }
+	}
+}
+
+pub type Walked = Vec<Result<DirEntry, WalkError>>;
+
+pub trait Reader: Read + Seek {}
+
+impl<T: Read + Seek> Reader for T {}
+
+pub trait Handle: Read + Write + Seek + Send {
+	fn set_len(&mut self, len: u64) -> io::Result<()>;
+	fn sync_all(&mut self) -> io::Result<()>;
+}
+
+impl Handle for std::fs::File {
+	fn set_len(&mut self, len: u64) -> io::Result<()> {
+		std::fs::File::set_len(self, len)
+	}
+
+	fn sync_all(&mut self) -> io::Result<()> {
+		std::fs::File::sync_all(self)
+	}
+}
+
+pub trait FileSystem: Sync {
+	fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+	fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+	fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+	fn hard_link(&self, from: &Path, to: &Path) -> io::Result<()>;
+	fn remove_file(&self, path: &Path) -> io::Result<()>;
+	fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+	fn set_permissions(&self, path: &Path, mode: u32) -> io::Result<()>;
+	fn symlink(&self, original: &Path, link: &Path) -> io::Result<()>;
+	fn open(&self, path: &Path) -> io::Result<Box<dyn Reader>>;
+	fn edit(&self, path: &Path) -> io::Result<Box<dyn Handle + '_>>;
+	fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send + '_>>;
+	fn set_mtime(&self, path: &Path, modified: i64) -> io::Result<()>;
+	fn available_space(&self, path: &Path) -> Option<u64>;
+	fn walk(&self, root: &Path, walk: Walk, filter: &mut dyn FnMut(&DirEntry) -> bool) -> Walked;
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+	fn is_symlink(&self, path: &Path) -> bool;
+	fn remove_dir(&self, path: &Path) -> io::Result<()>;
+	fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+	fn same_device(&self, from: &Path, to: &Path) -> bool;
+
+	fn exists(&self, path: &Path) -> bool {
+		self.metadata(path).is_ok()
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		self.metadata(path).is_ok_and(|metadata| !metadata.is_dir)
+	}
+
+	fn read_to_string(&self, path: &Path) -> io::Result<String> {
+		io::read_to_string(self.open(path)?)
+	}
+
+	fn entry_metadata(&self, entry: &DirEntry) -> io::Result<Metadata> {
+		self.metadata(entry.path())
+	}
+}
+
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+	fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+		Ok(Metadata::from(&std::fs::metadata(path)?))
+	}
+
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+		std::fs::canonicalize(path)
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+		std::fs::rename(from, to)
+	}
+
+	fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+		std::fs::copy(from, to)?;
+		let metadata = std::fs::metadata(from)?;
+		let mtime = FileTime::from_last_modification_time(&metadata);
+		filetime::set_file_mtime(to, mtime)
+	}
+
+	fn hard_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+		std::fs::hard_link(from, to)
+	}
+
+	fn remove_file(&self, path: &Path) -> io::Result<()> {
+		std::fs::remove_file(path)
+	}
+
+	fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+		std::fs::create_dir_all(path)
+	}
+
+	#[cfg(unix)]
+	fn set_permissions(&self, path: &Path, mode: u32) -> io::Result<()> {
+		std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+	}
+
+	#[cfg(not(unix))]
+	fn set_permissions(&self, _path: &Path, _mode: u32) -> io::Result<()> {
+		Ok(())
+	}
+
+	#[cfg(unix)]
+	fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+		std::os::unix::fs::symlink(original, link)
+	}
+
+	#[cfg(windows)]
+	fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+		std::os::windows::fs::symlink_file(original, link)
+	}
+
+	#[cfg(not(any(unix, windows)))]
+	fn symlink(&self, _original: &Path, _link: &Path) -> io::Result<()> {
+		Err(io::Error::from(io::ErrorKind::Unsupported))
+	}
+
+	fn open(&self, path: &Path) -> io::Result<Box<dyn Reader>> {
+		Ok(Box::new(std::fs::File::open(path)?))
+	}
+
+	fn edit(&self, path: &Path) -> io::Result<Box<dyn Handle + '_>> {
+		let file = std::fs::OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(false)
+			.open(path)?;
+		Ok(Box::new(file))
+	}
+
+	fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send + '_>> {
+		let file = std::fs::OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)?;
+		Ok(Box::new(file))
+	}
+
+	fn set_mtime(&self, path: &Path, modified: i64) -> io::Result<()> {
+		filetime::set_file_mtime(path, FileTime::from_unix_time(modified, 0))
+	}
+
+	#[cfg(unix)]
+	#[allow(clippy::unnecessary_cast)]
+	fn available_space(&self, path: &Path) -> Option<u64> {
+		let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+		let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+		match unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } {
+			0 => {
+				let stat = unsafe { stat.assume_init() };
+				Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+			}
+			_ => None,
+		}
+	}
+
+	#[cfg(not(unix))]
+	fn available_space(&self, _path: &Path) -> Option<u64> {
+		None
+	}
+
+	fn walk(&self, root: &Path, walk: Walk, filter: &mut dyn FnMut(&DirEntry) -> bool) -> Walked {
+		WalkDir::new(root)
+			.follow_links(walk.follow_links)
+			.min_depth(walk.min_depth)
+			.max_depth(walk.max_depth)
+			.into_iter()
+			.filter_entry(|entry| filter(&DirEntry::from(entry)))
+			.map(|entry| {
+				entry
+					.map(|entry| DirEntry::from(&entry))
+					.map_err(WalkError::from)
+			})
+			.collect()
+	}
+
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+		std::fs::read_link(path)
+	}
+
+	fn is_symlink(&self, path: &Path) -> bool {
+		path.is_symlink()
+	}
+
+	fn remove_dir(&self, path: &Path) -> io::Result<()> {
+		std::fs::remove_dir(path)
+	}
+
+	fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+		std::fs::write(path, contents)
+	}
+
+	#[cfg(unix)]
+	fn same_device(&self, from: &Path, to: &Path) -> bool {
+		match (std::fs::metadata(from), std::fs::metadata(to)) {
+			(Ok(from), Ok(to)) => from.dev() == to.dev(),
+			_ => false,
+		}
+	}
+
+	#[cfg(not(unix))]
+	fn same_device(&self, _from: &Path, _to: &Path) -> bool {
+		false
+	}
+}
+
+#[derive(Clone)]
+struct MemoryFile {
+	contents: Vec<u8>,
+	modified: i64,
+}
+
+struct MemoryHandle<'a> {
+	files: &'a Mutex<BTreeMap<PathBuf, MemoryFile>>,
+	path: PathBuf,
+	position: u64,
+	append: bool,
+}
+
+impl Read for MemoryHandle<'_> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let files = self.files.lock().unwrap();
+		let file = files.get(&self.path).ok_or_else(|| not_found(&self.path))?;
+		let start = file.contents.len().min(self.position as usize);
+		let read = buf.len().min(file.contents.len() - start);
+
+		buf[..read].copy_from_slice(&file.contents[start..start + read]);
+		self.position += read as u64;
+		Ok(read)
+	}
+}
+
+impl Write for MemoryHandle<'_> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut files = self.files.lock().unwrap();
+		let file = files
+			.get_mut(&self.path)
+			.ok_or_else(|| not_found(&self.path))?;
+
+		if self.append {
+			self.position = file.contents.len() as u64;
+		}
+
+		let start = self.position as usize;
+		let end = start + buf.len();
+
+		if file.contents.len() < end {
+			file.contents.resize(end, 0);
+		}
+
+		file.contents[start..end].copy_from_slice(buf);
+		self.position = end as u64;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Seek for MemoryHandle<'_> {
+	fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+		let len = self
+			.files
+			.lock()
+			.unwrap()
+			.get(&self.path)
+			.map_or(0, |file| file.contents.len() as u64);
+
+		let position = match position {
+			SeekFrom::Start(offset) => Some(offset),
+			SeekFrom::End(offset) => len.checked_add_signed(offset),
+			SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+		};
+
+		match position {
+			Some(position) => {
+				self.position = position;
+				Ok(position)
+			}
+			None => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+		}
+	}
+}
+
+impl Handle for MemoryHandle<'_> {
+	fn set_len(&mut self, len: u64) -> io::Result<()> {
+		let mut files = self.files.lock().unwrap();
+		let file = files
+			.get_mut(&self.path)
+			.ok_or_else(|| not_found(&self.path))?;
+		file.contents.resize(len as usize, 0);
+		Ok(())
+	}
+
+	fn sync_all(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[derive(Default)]
+pub struct MemoryFileSystem {
+	files: Mutex<BTreeMap<PathBuf, MemoryFile>>,
+	dirs: Mutex<BTreeSet<PathBuf>>,
+	devices: Vec<PathBuf>,
+}
+
+fn not_found(path: &Path) -> io::Error {
+	io::Error::new(io::ErrorKind::NotFound, path.display().to_string())
+}
+
+impl MemoryFileSystem {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_device(mut self, root: &Path) -> Self {
+		self.devices.push(root.to_path_buf());
+		self
+	}
+
+	pub fn add_file(&self, path: &Path, contents: &[u8], modified: i64) {
+		if let Some(parent) = path.parent() {
+			let _ = self.create_dir_all(parent);
+		}
+
+		let file = MemoryFile { contents: contents.to_vec(), modified };
+		self.files.lock().unwrap().insert(path.to_path_buf(), file);
+	}
+
+	pub fn contents(&self, path: &Path) -> Option<Vec<u8>> {
+		let files = self.files.lock().unwrap();
+		files.get(path).map(|file| file.contents.clone())
+	}
+
+	fn handle(&self, path: &Path, append: bool) -> MemoryHandle<'_> {
+		self.files
+			.lock()
+			.unwrap()
+			.entry(path.to_path_buf())
+			.or_insert(MemoryFile { contents: Vec::new(), modified: 0 });
+
+		MemoryHandle {
+			files: &self.files,
+			path: path.to_path_buf(),
+			position: 0,
+			append,
+		}
+	}
+
+	fn device(&self, path: &Path) -> Option<&Path> {
+		self.devices
+			.iter()
+			.filter(|root| path.starts_with(root))
+			.max_by_key(|root| root.components().count())
+			.map(PathBuf::as_path)
+	}
+}
+
+impl FileSystem for MemoryFileSystem {
+	fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+		if self.dirs.lock().unwrap().contains(path) {
+			return Ok(Metadata { len: 0, modified: 0, is_dir: true });
+		}
+
+		let files = self.files.lock().unwrap();
+		let file = files.get(path).ok_or_else(|| not_found(path))?;
+
+		Ok(Metadata {
+			len: file.contents.len() as u64,
+			modified: file.modified,
+			is_dir: false,
+		})
+	}
+
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+		match self.exists(path) {
+			true => Ok(path.to_path_buf()),
+			false => Err(not_found(path)),
+		}
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+		if self.device(from) != self.device(to) {
+			return Err(io::Error::from(io::ErrorKind::CrossesDevices));
+		}
+
+		let mut files = self.files.lock().unwrap();
+		let file = files.remove(from).ok_or_else(|| not_found(from))?;
+		files.insert(to.to_path_buf(), file);
+		Ok(())
+	}
+
+	fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+		let mut files = self.files.lock().unwrap();
+		let file = files.get(from).cloned().ok_or_else(|| not_found(from))?;
+		files.insert(to.to_path_buf(), file);
+		Ok(())
+	}
+
+	fn hard_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+		if self.device(from) != self.device(to) {
+			return Err(io::Error::from(io::ErrorKind::CrossesDevices));
+		}
+
+		self.copy(from, to)
+	}
+
+	fn remove_file(&self, path: &Path) -> io::Result<()> {
+		let mut files = self.files.lock().unwrap();
+		files
+			.remove(path)
+			.map(|_| ())
+			.ok_or_else(|| not_found(path))
+	}
+
+	fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+		let mut dirs = self.dirs.lock().unwrap();
+		dirs.extend(path.ancestors().map(Path::to_path_buf));
+		Ok(())
+	}
+
+	fn set_permissions(&self, path: &Path, _mode: u32) -> io::Result<()> {
+		match self.exists(path) {
+			true => Ok(()),
+			false => Err(not_found(path)),
+		}
+	}
+
+	fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+		self.copy(original, link)
+	}
+
+	fn open(&self, path: &Path) -> io::Result<Box<dyn Reader>> {
+		let contents = self.contents(path).ok_or_else(|| not_found(path))?;
+		Ok(Box::new(Cursor::new(contents)))
+	}
+
+	fn edit(&self, path: &Path) -> io::Result<Box<dyn Handle + '_>> {
+		Ok(Box::new(self.handle(path, false)))
+	}
+
+	fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send + '_>> {
+		Ok(Box::new(self.handle(path, true)))
+	}
+
+	fn set_mtime(&self, path: &Path, modified: i64) -> io::Result<()> {
+		let mut files = self.files.lock().unwrap();
+		let file = files.get_mut(path).ok_or_else(|| not_found(path))?;
+		file.modified = modified;
+		Ok(())
+	}
+
+	fn available_space(&self, _path: &Path) -> Option<u64> {
+		None
+	}
+
+	fn walk(&self, root: &Path, walk: Walk, filter: &mut dyn FnMut(&DirEntry) -> bool) -> Walked {
+		if !self.exists(root) {
+			let err = WalkError { path: Some(root.to_path_buf()), err: not_found(root) };
+			return vec![Err(err)];
+		}
+
+		let dirs = self.dirs.lock().unwrap().clone();
+		let paths: BTreeSet<_> = self
+			.files
+			.lock()
+			.unwrap()
+			.keys()
+			.chain(&dirs)
+			.filter(|path| path.starts_with(root))
+			.cloned()
+			.collect();
+
+		let mut pruned: Vec<PathBuf> = Vec::new();
+		let mut entries = Vec::new();
+
+		for path in paths {
+			let depth = path.components().count() - root.components().count();
+
+			if depth < walk.min_depth
+				|| depth > walk.max_depth
+				|| pruned.iter().any(|dir| path.starts_with(dir))
+			{
+				continue;
+			}
+
+			let entry = DirEntry { is_dir: dirs.contains(&path), path, depth, is_symlink: false };
+
+			match (filter(&entry), entry.is_dir) {
+				(true, _) => entries.push(Ok(entry)),
+				(false, true) => pruned.push(entry.into_path()),
+				(false, false) => {}
+			}
+		}
+
+		entries
+	}
+
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+		Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("{} is not a symlink", path.display()),
+		))
+	}
+
+	fn is_symlink(&self, _path: &Path) -> bool {
+		false
+	}
+
+	fn remove_dir(&self, path: &Path) -> io::Result<()> {
+		let populated = self
+			.files
+			.lock()
+			.unwrap()
+			.keys()
+			.chain(self.dirs.lock().unwrap().iter())
+			.any(|child| child != path && child.starts_with(path));
+
+		match populated {
+			true => Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty)),
+			false => match self.dirs.lock().unwrap().remove(path) {
+				true => Ok(()),
+				false => Err(not_found(path)),
+			},
+		}
+	}
+
+	fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+		self.add_file(path, contents, 0);
+		Ok(())
+	}
+
+	fn same_device(&self, from: &Path, to: &Path) -> bool {
+		self.device(from) == self.device(to)
+	}
+}
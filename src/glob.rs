@@ -0,0 +1,107 @@
+use std::path::Path;
+
+#[derive(Clone)]
+enum Token {
+	Char(char),
+	Any,
+	Star,
+	DoubleStar,
+	Class(bool, Vec<(char, char)>),
+}
+
+#[derive(Clone)]
+pub struct Glob(Vec<Token>);
+
+impl Glob {
+	pub fn parse(pattern: &str) -> Result<Self, String> {
+		let mut tokens = Vec::new();
+		let mut chars = pattern.chars().peekable();
+
+		while let Some(c) = chars.next() {
+			let token = match c {
+				'?' => Token::Any,
+				'*' if chars.peek() == Some(&'*') => {
+					chars.next();
+					Token::DoubleStar
+				}
+				'*' => Token::Star,
+				'[' => {
+					let negated = chars.next_if(|c| matches!(c, '!' | '^')).is_some();
+					let mut ranges = Vec::new();
+
+					loop {
+						let start = match chars.next() {
+							Some(']') if !ranges.is_empty() => break,
+							Some(c) => c,
+							None => return Err(format!("Unclosed [ in {pattern}")),
+						};
+
+						let end = match chars.next_if_eq(&'-') {
+							Some(_) => chars.next().ok_or(format!("Unclosed [ in {pattern}"))?,
+							None => start,
+						};
+
+						ranges.push((start, end));
+					}
+
+					Token::Class(negated, ranges)
+				}
+				'\\' => Token::Char(chars.next().unwrap_or('\\')),
+				c => Token::Char(c),
+			};
+
+			tokens.push(token);
+		}
+
+		Ok(Self(tokens))
+	}
+
+	fn matches_str(tokens: &[Token], text: &[char]) -> bool {
+		let Some((token, rest)) = tokens.split_first() else {
+			return text.is_empty();
+		};
+
+		match token {
+			Token::DoubleStar => (0..=text.len()).any(|idx| Self::matches_str(rest, &text[idx..])),
+			Token::Star => (0..=text.len())
+				.take_while(|idx| *idx == 0 || text[idx - 1] != '/')
+				.any(|idx| Self::matches_str(rest, &text[idx..])),
+			_ => match text.split_first() {
+				Some((c, text)) if Self::matches_char(token, *c) => Self::matches_str(rest, text),
+				_ => false,
+			},
+		}
+	}
+
+	fn matches_char(token: &Token, c: char) -> bool {
+		match token {
+			Token::Char(expected) => *expected == c,
+			Token::Any => c != '/',
+			Token::Class(negated, ranges) => {
+				c != '/'
+					&& ranges
+						.iter()
+						.any(|(start, end)| (*start..=*end).contains(&c))
+						!= *negated
+			}
+			Token::Star | Token::DoubleStar => unreachable!(),
+		}
+	}
+
+	pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+		let mut full = path.to_string_lossy().replace('\\', "/");
+
+		if is_dir {
+			full.push('/');
+		}
+
+		let name = path
+			.file_name()
+			.map(|name| name.to_string_lossy())
+			.unwrap_or_default();
+
+		[full.as_str(), &name]
+			.iter()
+			.any(|text| Self::matches_str(&self.0, &text.chars().collect::<Vec<_>>()))
+	}
+}
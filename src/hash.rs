@@ -0,0 +1,207 @@
+use std::io;
+use std::io::Read;
+
+pub const CHUNK: usize = 1 << 20;
+
+const K: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const H: [u32; 8] = [
+	0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub type Hash = [u8; 32];
+
+struct Sha256 {
+	state: [u32; 8],
+	block: [u8; 64],
+	filled: usize,
+	len: u64,
+}
+
+impl Sha256 {
+	fn new() -> Self {
+		Self { state: H, block: [0; 64], filled: 0, len: 0 }
+	}
+
+	fn compress(&mut self) {
+		let mut w = [0u32; 64];
+
+		for (idx, word) in self.block.chunks_exact(4).enumerate() {
+			w[idx] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+		}
+
+		for idx in 16..64 {
+			let s0 =
+				w[idx - 15].rotate_right(7) ^ w[idx - 15].rotate_right(18) ^ (w[idx - 15] >> 3);
+			let s1 = w[idx - 2].rotate_right(17) ^ w[idx - 2].rotate_right(19) ^ (w[idx - 2] >> 10);
+			w[idx] = w[idx - 16]
+				.wrapping_add(s0)
+				.wrapping_add(w[idx - 7])
+				.wrapping_add(s1);
+		}
+
+		let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+
+		for idx in 0..64 {
+			let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+			let ch = (e & f) ^ (!e & g);
+			let t1 = h
+				.wrapping_add(s1)
+				.wrapping_add(ch)
+				.wrapping_add(K[idx])
+				.wrapping_add(w[idx]);
+			let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+			let maj = (a & b) ^ (a & c) ^ (b & c);
+			let t2 = s0.wrapping_add(maj);
+
+			h = g;
+			g = f;
+			f = e;
+			e = d.wrapping_add(t1);
+			d = c;
+			c = b;
+			b = a;
+			a = t1.wrapping_add(t2);
+		}
+
+		for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+			*state = state.wrapping_add(value);
+		}
+	}
+
+	fn update(&mut self, mut data: &[u8]) {
+		self.len += data.len() as u64;
+
+		while !data.is_empty() {
+			let take = data.len().min(64 - self.filled);
+			self.block[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+			self.filled += take;
+			data = &data[take..];
+
+			if self.filled == 64 {
+				self.compress();
+				self.filled = 0;
+			}
+		}
+	}
+
+	fn finish(mut self) -> Hash {
+		let bits = self.len.wrapping_mul(8);
+		self.update(&[0x80]);
+
+		while self.filled != 56 {
+			self.update(&[0]);
+		}
+
+		self.update(&bits.to_be_bytes());
+
+		let mut hash = [0; 32];
+
+		for (bytes, word) in hash.chunks_exact_mut(4).zip(self.state) {
+			bytes.copy_from_slice(&word.to_be_bytes());
+		}
+
+		hash
+	}
+}
+
+pub fn hash_reader(reader: impl Read) -> io::Result<Hash> {
+	hash_reader_with(reader, |_| {})
+}
+
+pub fn hash_reader_with(mut reader: impl Read, mut progress: impl FnMut(u64)) -> io::Result<Hash> {
+	let mut buffer = vec![0; CHUNK];
+	let mut sha = Sha256::new();
+
+	loop {
+		match reader.read(&mut buffer)? {
+			0 => return Ok(sha.finish()),
+			read => {
+				sha.update(&buffer[..read]);
+				progress(sha.len);
+			}
+		}
+	}
+}
+
+pub fn hash_prefix(reader: impl Read) -> io::Result<Hash> {
+	hash_reader(reader.take(CHUNK as u64))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hex(hash: Hash) -> String {
+		hash.iter().map(|byte| format!("{byte:02x}")).collect()
+	}
+
+	fn sha256(data: &[u8]) -> String {
+		hex(hash_reader(data).unwrap())
+	}
+
+	fn pattern(len: usize) -> Vec<u8> {
+		(0..len).map(|idx| (idx % 251) as u8).collect()
+	}
+
+	#[test]
+	fn hashes_known_answers() {
+		assert_eq!(
+			sha256(b""),
+			"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+		);
+		assert_eq!(
+			sha256(b"abc"),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+		assert_eq!(
+			sha256(&[b'a'; 1_000_000]),
+			"cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+		);
+	}
+
+	#[test]
+	fn pads_at_block_boundaries() {
+		assert_eq!(
+			sha256(&[b'a'; 55]),
+			"9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"
+		);
+		assert_eq!(
+			sha256(&[b'a'; 56]),
+			"b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"
+		);
+		assert_eq!(
+			sha256(&[b'a'; 64]),
+			"ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"
+		);
+	}
+
+	#[test]
+	fn hashes_across_chunks() {
+		let data = pattern(2 * CHUNK + 3);
+		let mut progress = Vec::new();
+		let hash = hash_reader_with(&data[..], |hashed| progress.push(hashed)).unwrap();
+
+		assert_eq!(
+			hex(hash),
+			"9d5bd11e1a0db7e737b58c7b3c0eaabeab2d7adb4b328b455607f2c50ad029d2"
+		);
+		assert_eq!(
+			progress,
+			[CHUNK as u64, 2 * CHUNK as u64, data.len() as u64]
+		);
+		assert_eq!(
+			hex(hash_prefix(&data[..]).unwrap()),
+			"631b84027d6b9e52b539c4e8373622d23032dfadc64d60af87339c9037e4f769"
+		);
+	}
+}
@@ -0,0 +1,134 @@
+use crate::fs::DirEntry;
+use crate::fs::FileSystem;
+use crate::Glob;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+const IGNORE_FILE: &str = ".tfignore";
+
+struct Rule {
+	glob: Glob,
+	negated: bool,
+}
+
+pub struct Ignore<'a> {
+	root: PathBuf,
+	rules: HashMap<PathBuf, Vec<Rule>>,
+	fs: &'a dyn FileSystem,
+}
+
+fn parse(rules: &str) -> Vec<Rule> {
+	rules
+		.lines()
+		.map(str::trim)
+		.filter(|rule| !rule.is_empty() && !rule.starts_with('#'))
+		.filter_map(|rule| {
+			let (negated, rule) = match rule.strip_prefix('!') {
+				Some(rule) => (true, rule),
+				None => (false, rule),
+			};
+			let glob = Glob::parse(rule.trim_start_matches('/')).ok()?;
+
+			Some(Rule { glob, negated })
+		})
+		.collect()
+}
+
+impl<'a> Ignore<'a> {
+	pub fn new(root: &Path, fs: &'a dyn FileSystem) -> Self {
+		Self { root: root.to_path_buf(), rules: HashMap::new(), fs }
+	}
+
+	pub fn ignored(&mut self, entry: &DirEntry) -> bool {
+		if entry.file_name() == IGNORE_FILE {
+			return true;
+		}
+
+		let path = entry.path();
+		let is_dir = entry.is_dir();
+
+		path.ancestors()
+			.skip(1)
+			.take_while(|dir| dir.starts_with(&self.root))
+			.find_map(|dir| {
+				let rules = self.rules.entry(dir.to_path_buf()).or_insert_with(|| {
+					self.fs
+						.read_to_string(&dir.join(IGNORE_FILE))
+						.map(|rules| parse(&rules))
+						.unwrap_or_default()
+				});
+				let relative = path.strip_prefix(dir).ok()?;
+
+				rules
+					.iter()
+					.rev()
+					.find(|rule| rule.glob.matches(relative, is_dir))
+					.map(|rule| !rule.negated)
+			})
+			.unwrap_or(false)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fs::MemoryFileSystem;
+	use crate::fs::Walk;
+
+	fn walk(fs: &MemoryFileSystem, files: &[(&str, &str)]) -> Vec<PathBuf> {
+		for (path, contents) in files {
+			fs.add_file(Path::new(path), contents.as_bytes(), 0);
+		}
+
+		let root = Path::new("/source");
+		let mut ignore = Ignore::new(root, fs);
+
+		fs.walk(root, Walk::default(), &mut |entry| !ignore.ignored(entry))
+			.into_iter()
+			.filter_map(|entry| entry.ok())
+			.filter(|entry| !entry.is_dir())
+			.map(DirEntry::into_path)
+			.collect()
+	}
+
+	#[test]
+	fn negated_rules_reinclude_files() {
+		let fs = MemoryFileSystem::new();
+		let walked = walk(
+			&fs,
+			&[
+				("/source/.tfignore", "*.tmp\n!keep.tmp\n"),
+				("/source/drop.tmp", ""),
+				("/source/keep.tmp", ""),
+				("/source/photo.jpg", ""),
+			],
+		);
+
+		assert_eq!(
+			walked,
+			[
+				PathBuf::from("/source/keep.tmp"),
+				PathBuf::from("/source/photo.jpg")
+			]
+		);
+	}
+
+	#[test]
+	fn nested_files_override_parent_rules() {
+		let fs = MemoryFileSystem::new();
+		let walked = walk(
+			&fs,
+			&[
+				("/source/.tfignore", "*.jpg\ncache/\n"),
+				("/source/cache/photo.png", ""),
+				("/source/trip/.tfignore", "!*.jpg\nraw/*.jpg\n"),
+				("/source/trip/photo.jpg", ""),
+				("/source/trip/raw/photo.jpg", ""),
+				("/source/photo.jpg", ""),
+			],
+		);
+
+		assert_eq!(walked, [PathBuf::from("/source/trip/photo.jpg")]);
+	}
+}
@@ -0,0 +1,386 @@
+#[cfg(any(unix, windows))]
+use std::ffi::OsString;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Write;
+use std::iter::Peekable;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStringExt;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::Chars;
+
+pub enum Value {
+	Null,
+	Number(i64),
+	String(String),
+	Array(Vec<Value>),
+	Object(Vec<(String, Value)>),
+}
+
+impl Value {
+	pub fn object<const N: usize>(entries: [(&str, Value); N]) -> Self {
+		Value::Object(
+			entries
+				.into_iter()
+				.map(|(key, value)| (key.to_string(), value))
+				.collect(),
+		)
+	}
+}
+
+impl Value {
+	pub fn parse(text: &str) -> Option<Self> {
+		let mut parser = Parser { chars: text.chars().peekable() };
+		let value = parser.value()?;
+		parser.whitespace();
+
+		parser.chars.peek().is_none().then_some(value)
+	}
+
+	pub fn get(&self, key: &str) -> Option<&Value> {
+		match self {
+			Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Value::String(text) => Some(text),
+			_ => None,
+		}
+	}
+
+	pub fn as_i64(&self) -> Option<i64> {
+		match self {
+			Value::Number(number) => Some(*number),
+			_ => None,
+		}
+	}
+
+	pub fn as_u64(&self) -> Option<u64> {
+		self.as_i64().and_then(|number| u64::try_from(number).ok())
+	}
+
+	pub fn as_path(&self) -> Option<PathBuf> {
+		match self {
+			Value::String(path) => Some(PathBuf::from(path)),
+			Value::Object(_) => unhex(self.get("hex")?.as_str()?),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(unix)]
+fn hex(path: &Path) -> String {
+	path.as_os_str()
+		.as_bytes()
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}
+
+#[cfg(unix)]
+fn unhex(hex: &str) -> Option<PathBuf> {
+	let bytes = (0..hex.len())
+		.step_by(2)
+		.map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok())
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+#[cfg(windows)]
+fn hex(path: &Path) -> String {
+	path.as_os_str()
+		.encode_wide()
+		.map(|unit| format!("{unit:04x}"))
+		.collect()
+}
+
+#[cfg(windows)]
+fn unhex(hex: &str) -> Option<PathBuf> {
+	let units = (0..hex.len())
+		.step_by(4)
+		.map(|idx| u16::from_str_radix(hex.get(idx..idx + 4)?, 16).ok())
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(PathBuf::from(OsString::from_wide(&units)))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn hex(path: &Path) -> String {
+	path.to_string_lossy()
+		.bytes()
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unhex(hex: &str) -> Option<PathBuf> {
+	let bytes = (0..hex.len())
+		.step_by(2)
+		.map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok())
+		.collect::<Option<Vec<_>>>()?;
+
+	String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+struct Parser<'a> {
+	chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+	fn whitespace(&mut self) {
+		while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+	}
+
+	fn expect(&mut self, expected: char) -> Option<()> {
+		self.whitespace();
+		self.chars.next_if_eq(&expected).map(|_| ())
+	}
+
+	fn value(&mut self) -> Option<Value> {
+		self.whitespace();
+
+		match self.chars.peek()? {
+			'"' => self.string().map(Value::String),
+			'-' | '0'..='9' => self.number().map(Value::Number),
+			'[' => self.array(),
+			'{' => self.object(),
+			'n' => "null"
+				.chars()
+				.try_for_each(|c| self.chars.next_if_eq(&c).map(|_| ()))
+				.map(|_| Value::Null),
+			_ => None,
+		}
+	}
+
+	fn string(&mut self) -> Option<String> {
+		self.expect('"')?;
+		let mut text = String::new();
+
+		loop {
+			match self.chars.next()? {
+				'"' => return Some(text),
+				'\\' => match self.chars.next()? {
+					'b' => text.push('\u{8}'),
+					'f' => text.push('\u{c}'),
+					'n' => text.push('\n'),
+					'r' => text.push('\r'),
+					't' => text.push('\t'),
+					'u' => {
+						let high = self.hex()?;
+						let code = match high {
+							0xD800..=0xDBFF => {
+								self.chars.next_if_eq(&'\\')?;
+								self.chars.next_if_eq(&'u')?;
+								let low = match self.hex()? {
+									low @ 0xDC00..=0xDFFF => low,
+									_ => return None,
+								};
+
+								0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+							}
+							code => code,
+						};
+
+						text.push(char::from_u32(code)?);
+					}
+					c => text.push(c),
+				},
+				c => text.push(c),
+			}
+		}
+	}
+
+	fn number(&mut self) -> Option<i64> {
+		let mut digits = String::new();
+
+		if let Some(sign) = self.chars.next_if_eq(&'-') {
+			digits.push(sign);
+		}
+
+		while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+			digits.push(c);
+		}
+
+		digits.parse().ok()
+	}
+
+	fn hex(&mut self) -> Option<u32> {
+		(0..4).try_fold(0, |code, _| {
+			Some(code * 16 + self.chars.next()?.to_digit(16)?)
+		})
+	}
+
+	fn array(&mut self) -> Option<Value> {
+		self.expect('[')?;
+		let mut values = Vec::new();
+
+		if self.expect(']').is_some() {
+			return Some(Value::Array(values));
+		}
+
+		loop {
+			values.push(self.value()?);
+
+			if self.expect(']').is_some() {
+				return Some(Value::Array(values));
+			}
+
+			self.expect(',')?;
+		}
+	}
+
+	fn object(&mut self) -> Option<Value> {
+		self.expect('{')?;
+		let mut entries = Vec::new();
+
+		if self.expect('}').is_some() {
+			return Some(Value::Object(entries));
+		}
+
+		loop {
+			self.whitespace();
+			let key = self.string()?;
+			self.expect(':')?;
+			entries.push((key, self.value()?));
+
+			if self.expect('}').is_some() {
+				return Some(Value::Object(entries));
+			}
+
+			self.expect(',')?;
+		}
+	}
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+	fn from(value: Option<T>) -> Self {
+		value.map_or(Value::Null, Into::into)
+	}
+}
+
+impl From<usize> for Value {
+	fn from(value: usize) -> Self {
+		Value::from(value as u64)
+	}
+}
+
+impl From<u64> for Value {
+	fn from(value: u64) -> Self {
+		Value::Number(i64::try_from(value).unwrap_or(i64::MAX))
+	}
+}
+
+impl From<i64> for Value {
+	fn from(value: i64) -> Self {
+		Value::Number(value)
+	}
+}
+
+impl From<String> for Value {
+	fn from(value: String) -> Self {
+		Value::String(value)
+	}
+}
+
+impl From<&Path> for Value {
+	fn from(path: &Path) -> Self {
+		match path.to_str() {
+			Some(path) => Value::String(path.to_string()),
+			None => Value::object([("hex", Value::String(hex(path)))]),
+		}
+	}
+}
+
+fn escape(f: &mut Formatter, text: &str) -> fmt::Result {
+	f.write_char('"')?;
+
+	for c in text.chars() {
+		match c {
+			'"' => f.write_str("\\\"")?,
+			'\\' => f.write_str("\\\\")?,
+			'\n' => f.write_str("\\n")?,
+			'\r' => f.write_str("\\r")?,
+			'\t' => f.write_str("\\t")?,
+			c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+			c => f.write_char(c)?,
+		}
+	}
+
+	f.write_char('"')
+}
+
+impl Display for Value {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Value::Null => f.write_str("null"),
+			Value::Number(number) => write!(f, "{number}"),
+			Value::String(text) => escape(f, text),
+			Value::Array(values) => {
+				f.write_char('[')?;
+
+				for (idx, value) in values.iter().enumerate() {
+					if idx > 0 {
+						f.write_char(',')?;
+					}
+
+					write!(f, "{value}")?;
+				}
+
+				f.write_char(']')
+			}
+			Value::Object(entries) => {
+				f.write_char('{')?;
+
+				for (idx, (key, value)) in entries.iter().enumerate() {
+					if idx > 0 {
+						f.write_char(',')?;
+					}
+
+					escape(f, key)?;
+					write!(f, ":{value}")?;
+				}
+
+				f.write_char('}')
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_surrogate_pairs() {
+		let value = Value::parse(r#""\ud83d\udcf7""#);
+		assert_eq!(value.as_ref().and_then(Value::as_str), Some("\u{1F4F7}"));
+	}
+
+	#[test]
+	fn parses_signed_numbers() {
+		let value = Value::parse(r#"{"size":42,"modified":-3600}"#).unwrap();
+		assert_eq!(value.get("size").and_then(Value::as_u64), Some(42));
+		assert_eq!(value.get("modified").and_then(Value::as_i64), Some(-3600));
+		assert_eq!(value.get("modified").and_then(Value::as_u64), None);
+		assert_eq!(Value::from(-3600i64).to_string(), "-3600");
+		assert!(Value::parse("-").is_none());
+	}
+
+	#[test]
+	fn rejects_invalid_low_surrogates() {
+		assert!(Value::parse(r#""\ud83d\u0041""#).is_none());
+		assert!(Value::parse(r#""\ud83d\ud83d""#).is_none());
+	}
+}
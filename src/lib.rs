@@ -0,0 +1,4063 @@
+mod archive;
+mod aspect;
+mod checksums;
+mod diff;
+mod duration;
+mod exec;
+mod exif;
+mod filename;
+pub mod fs;
+mod glob;
+mod hash;
+mod ignore;
+mod json;
+mod locale;
+mod plan;
+mod sniff;
+mod stats;
+mod template;
+mod timezone;
+mod tree;
+mod unicode;
+
+use checksums::ChecksumDb;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use clap::Args;
+use clap::ValueEnum;
+use colored::Colorize;
+use diff::Change;
+use diff::Diff;
+use exec::Exec;
+use exif::Exif;
+use fs::DirEntry;
+use fs::FileSystem;
+use fs::Metadata;
+use fs::RealFileSystem;
+use fs::Walk;
+pub use glob::Glob;
+use hash::Hash;
+use ignore::Ignore;
+use json::Value;
+pub use locale::Locale;
+use plan::Plan;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::ParallelIterator;
+use sniff::sniff;
+use stats::Stats;
+use std::cmp;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+pub use template::Template;
+use thiserror::Error;
+pub use timezone::Timezone;
+use tree::Tree;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+	"arw", "cr2", "cr3", "dng", "heic", "heif", "nef", "orf", "raf", "rw2",
+];
+
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+const SIDECAR_EXTENSIONS: &[&str] = &["xmp", "aae", "thm"];
+
+const LIVE_IMAGE_EXTENSIONS: &[&str] = &["heic", "heif"];
+
+const LIVE_VIDEO_EXTENSIONS: &[&str] = &["mov"];
+
+const LAST_RUN: &str = ".tf-last-run";
+
+const MARKER: &str = ".tf-archive";
+
+#[derive(Error, Debug)]
+pub enum Error {
+	#[error("Walkdir error: {0}")]
+	WalkDir(#[from] fs::WalkError),
+
+	#[error("IO error: {0}")]
+	Io(#[from] io::Error),
+
+	#[error("Thread pool error: {0}")]
+	ThreadPool(#[from] rayon::ThreadPoolBuildError),
+
+	#[error("Skipping file: {0}")]
+	Skipping(PathBuf),
+
+	#[error("File {0} has no name")]
+	NoName(PathBuf),
+
+	#[error("File name template expands to an empty name for {0}")]
+	EmptyName(PathBuf),
+
+	#[error("Invalid date for {0}")]
+	DateTime(PathBuf),
+
+	#[error("Unknown MIME type for {0}")]
+	Mime(PathBuf),
+
+	#[error("{0} is a directory")]
+	Dir(PathBuf),
+
+	#[error("Could not copy {0} across devices")]
+	CrossDevice(PathBuf),
+
+	#[error("Cannot hard link {0} to a different filesystem")]
+	HardlinkCrossDevice(PathBuf),
+
+	#[error("File {0} already exists")]
+	DestinationExists(PathBuf),
+
+	#[error("Destination {0} is inside source {1}")]
+	DestinationInsideSource(PathBuf, PathBuf),
+
+	#[error("Destination {0} has no .tf-archive marker, run `tf init {0}` to create it")]
+	MissingMarker(PathBuf),
+
+	#[error("Not enough space on {0}: {1} bytes needed, {2} bytes available")]
+	NoSpace(PathBuf, u64, u64),
+
+	#[error("No EXIF date in {0}")]
+	Exif(PathBuf),
+
+	#[error("No date in the name of {0}")]
+	FilenameDate(PathBuf),
+
+	#[error("Size of {0} is out of range")]
+	Size(PathBuf),
+
+	#[error("Skipping empty file: {0}")]
+	Empty(PathBuf),
+
+	#[error("Skipping corrupt file: {0}")]
+	Corrupt(PathBuf),
+
+	#[error("Date of {0} is out of range")]
+	DateRange(PathBuf),
+
+	#[error("Skipping symlink: {0}")]
+	Symlink(PathBuf),
+
+	#[error("skipped (vanished): {0}")]
+	Vanished(PathBuf),
+
+	#[error("Permission denied: {0}")]
+	Permission(PathBuf),
+
+	#[error("{0} is a sidecar")]
+	Sidecar(PathBuf),
+
+	#[error("{0} is part of a live photo")]
+	LivePhoto(PathBuf),
+
+	#[error("Copy {0} does not match its source")]
+	Verify(PathBuf),
+
+	#[error("Command for {0} failed: {1}")]
+	Exec(PathBuf, String),
+
+	#[error("Aborting after failure on {0}")]
+	Strict(PathBuf),
+
+	#[error("{0} file(s) failed")]
+	Failed(usize),
+
+	#[error("Required capability {0} is unavailable")]
+	Unavailable(Capability),
+
+	#[error("Invalid config {0}: {1} (command-line flags take precedence over config values)")]
+	Config(PathBuf, String),
+}
+
+impl Error {
+	fn kind(&self) -> &'static str {
+		match self {
+			Error::WalkDir(_) => "walk_dir",
+			Error::Io(_) => "io",
+			Error::ThreadPool(_) => "thread_pool",
+			Error::Skipping(_) => "skipping",
+			Error::NoName(_) => "no_name",
+			Error::EmptyName(_) => "empty_name",
+			Error::DateTime(_) => "date_time",
+			Error::Mime(_) => "mime",
+			Error::Dir(_) => "dir",
+			Error::CrossDevice(_) => "cross_device",
+			Error::HardlinkCrossDevice(_) => "hardlink_cross_device",
+			Error::DestinationExists(_) => "destination_exists",
+			Error::DestinationInsideSource(_, _) => "destination_inside_source",
+			Error::MissingMarker(_) => "missing_marker",
+			Error::NoSpace(_, _, _) => "no_space",
+			Error::Exif(_) => "exif",
+			Error::FilenameDate(_) => "filename_date",
+			Error::Size(_) => "size",
+			Error::Empty(_) => "empty",
+			Error::Corrupt(_) => "corrupt",
+			Error::DateRange(_) => "date_range",
+			Error::Symlink(_) => "symlink",
+			Error::Vanished(_) => "vanished",
+			Error::Permission(_) => "permission",
+			Error::Sidecar(_) => "sidecar",
+			Error::LivePhoto(_) => "live_photo",
+			Error::Verify(_) => "verify",
+			Error::Exec(_, _) => "exec",
+			Error::Strict(_) => "strict",
+			Error::Failed(_) => "failed",
+			Error::Unavailable(_) => "unavailable",
+			Error::Config(_, _) => "config",
+		}
+	}
+
+	fn path(&self) -> Option<&Path> {
+		match self {
+			Error::WalkDir(err) => err.path.as_deref(),
+			Error::Io(_) | Error::ThreadPool(_) | Error::Failed(_) | Error::Unavailable(_) => None,
+			Error::Skipping(path)
+			| Error::NoName(path)
+			| Error::EmptyName(path)
+			| Error::DateTime(path)
+			| Error::Mime(path)
+			| Error::Dir(path)
+			| Error::CrossDevice(path)
+			| Error::HardlinkCrossDevice(path)
+			| Error::DestinationExists(path)
+			| Error::DestinationInsideSource(path, _)
+			| Error::MissingMarker(path)
+			| Error::NoSpace(path, _, _)
+			| Error::Exif(path)
+			| Error::FilenameDate(path)
+			| Error::Size(path)
+			| Error::Empty(path)
+			| Error::Corrupt(path)
+			| Error::DateRange(path)
+			| Error::Symlink(path)
+			| Error::Vanished(path)
+			| Error::Permission(path)
+			| Error::Sidecar(path)
+			| Error::LivePhoto(path)
+			| Error::Verify(path)
+			| Error::Exec(path, _)
+			| Error::Strict(path)
+			| Error::Config(path, _) => Some(path),
+		}
+	}
+
+	pub fn to_json(&self) -> String {
+		Value::object([
+			("kind", Value::from(self.kind().to_string())),
+			("path", Value::from(self.path())),
+			("message", Value::from(self.to_string())),
+		])
+		.to_string()
+	}
+
+	fn action(&self) -> Option<Action> {
+		match self {
+			Error::Dir(_) | Error::Sidecar(_) | Error::LivePhoto(_) => None,
+			Error::Skipping(_)
+			| Error::Mime(_)
+			| Error::Size(_)
+			| Error::Empty(_)
+			| Error::Corrupt(_)
+			| Error::DateRange(_)
+			| Error::Symlink(_)
+			| Error::Vanished(_) => Some(Action::Skipped),
+			_ => Some(Action::Failed),
+		}
+	}
+}
+
+#[derive(Args, Clone)]
+pub struct Config {
+	#[arg(short, long, required_unless_present_any = ["from_stdin", "files_from"])]
+	pub source: Vec<PathBuf>,
+
+	#[arg(long, conflicts_with = "files_from")]
+	pub from_stdin: bool,
+
+	#[arg(long)]
+	pub files_from: Option<PathBuf>,
+
+	#[arg(short, long, required = true)]
+	pub destination: PathBuf,
+
+	#[arg(long)]
+	pub require_marker: bool,
+
+	#[arg(short, long, value_parser = parse_person)]
+	pub person: Option<String>,
+
+	#[arg(long, value_enum)]
+	pub person_from: Option<PersonFrom>,
+
+	#[arg(long, conflicts_with = "person_from")]
+	pub owner_from_exif_artist: bool,
+
+	#[arg(short = 'y', long)]
+	pub dry_run: bool,
+
+	#[arg(long, value_enum)]
+	pub mode: Option<Mode>,
+
+	#[arg(short, long, conflicts_with = "mode")]
+	pub copy: bool,
+
+	#[arg(long, conflicts_with_all = ["copy", "mode"])]
+	pub hardlink: bool,
+
+	#[arg(long, value_enum, default_value_t = Conflict::Skip, alias = "collision-policy")]
+	pub on_conflict: Conflict,
+
+	#[arg(long)]
+	pub case_insensitive: bool,
+
+	#[arg(short, long, default_value = "{kind}/{person}/{year}/{month}", value_parser = Template::parse)]
+	pub template: Template,
+
+	#[arg(long, value_enum, default_value_t = MonthFormat::Name)]
+	pub month_format: MonthFormat,
+
+	#[arg(long, default_value = "en", value_parser = Locale::parse)]
+	pub locale: Locale,
+
+	#[arg(long, default_value = "local", allow_hyphen_values = true, value_parser = Timezone::parse)]
+	pub timezone: Timezone,
+
+	#[arg(long, value_parser = clap::value_parser!(u32).range(0..24))]
+	pub same_day_threshold: Option<u32>,
+
+	#[arg(long, value_enum, default_value_t = DateFrom::Exif)]
+	pub date_from: DateFrom,
+
+	#[arg(long)]
+	pub filename_pattern: Vec<String>,
+
+	#[arg(long, value_parser = parse_map)]
+	pub map: Vec<(String, Extension)>,
+
+	#[arg(long)]
+	pub image_dir: Option<String>,
+
+	#[arg(long)]
+	pub video_dir: Option<String>,
+
+	#[arg(long)]
+	pub audio_dir: Option<String>,
+
+	#[arg(long, value_delimiter = ',', value_parser = parse_extension)]
+	pub image_ext: Vec<String>,
+
+	#[arg(long, value_delimiter = ',', value_parser = parse_extension)]
+	pub video_ext: Vec<String>,
+
+	#[arg(long, value_delimiter = ',', value_parser = parse_extension)]
+	pub audio_ext: Vec<String>,
+
+	#[arg(skip)]
+	pub overrides: HashMap<String, Extension>,
+
+	#[arg(long, value_delimiter = ',', value_parser = parse_mime)]
+	pub exclude_mime: Vec<String>,
+
+	#[arg(long, value_delimiter = ',', value_parser = parse_extension)]
+	pub only_ext: Vec<String>,
+
+	#[arg(short, long)]
+	pub quiet: bool,
+
+	#[arg(skip)]
+	pub format: Format,
+
+	#[arg(long, conflicts_with_all = ["from_stdin", "files_from"])]
+	pub watch: bool,
+
+	#[arg(long, default_value_t = 2)]
+	pub watch_interval: u64,
+
+	#[arg(skip)]
+	pub paths: Vec<PathBuf>,
+
+	#[arg(long)]
+	pub dedup: bool,
+
+	#[arg(long)]
+	pub newer_than_dest: bool,
+
+	#[arg(long)]
+	pub checksum_db: Option<PathBuf>,
+
+	#[arg(short, long, value_parser = Glob::parse)]
+	pub exclude: Vec<Glob>,
+
+	#[arg(long)]
+	pub no_ignore: bool,
+
+	#[arg(long, overrides_with = "include_hidden")]
+	pub ignore_hidden: bool,
+
+	#[arg(long, overrides_with = "ignore_hidden")]
+	pub include_hidden: bool,
+
+	#[arg(long, value_parser = parse_size)]
+	pub min_size: Option<u64>,
+
+	#[arg(long, value_parser = parse_size)]
+	pub max_size: Option<u64>,
+
+	#[arg(long)]
+	pub skip_empty: bool,
+
+	#[arg(long)]
+	pub validate_media: bool,
+
+	#[arg(long, value_parser = parse_date)]
+	pub since: Option<NaiveDate>,
+
+	#[arg(long, value_parser = parse_date)]
+	pub until: Option<NaiveDate>,
+
+	#[arg(long)]
+	pub since_last_run: bool,
+
+	#[arg(skip)]
+	pub last_run: Option<i64>,
+
+	#[arg(long)]
+	pub report: Option<PathBuf>,
+
+	#[arg(short, long)]
+	pub verbose: bool,
+
+	#[arg(short, long)]
+	pub journal: Option<PathBuf>,
+
+	#[arg(long, requires = "journal")]
+	pub resume: bool,
+
+	#[arg(long)]
+	pub no_sidecars: bool,
+
+	#[arg(long, conflicts_with = "template")]
+	pub flatten: bool,
+
+	#[arg(long, conflicts_with_all = ["template", "flatten"])]
+	pub preserve_tree: bool,
+
+	#[arg(long, value_enum, conflicts_with_all = ["template", "flatten", "preserve_tree"])]
+	pub dest_structure: Option<DestStructure>,
+
+	#[arg(long, value_enum, conflicts_with_all = ["hardlink", "pair_live_photos"])]
+	pub archive: Option<Archive>,
+
+	#[arg(long, value_parser = Exec::parse, conflicts_with = "archive")]
+	pub exec: Option<Exec>,
+
+	#[arg(long, conflicts_with_all = ["copy", "hardlink", "archive"])]
+	pub link_back: bool,
+
+	#[arg(long)]
+	pub follow_symlinks: bool,
+
+	#[arg(long, value_enum, default_value_t = Symlink::Skip)]
+	pub symlink: Symlink,
+
+	#[arg(long, value_parser = Template::parse_name)]
+	pub rename_pattern: Option<Template>,
+
+	#[arg(long, value_parser = Template::parse_file_name, conflicts_with = "rename_pattern")]
+	pub rename_on_move: Option<Template>,
+
+	#[arg(long)]
+	pub lowercase_extensions: bool,
+
+	#[arg(long)]
+	pub normalize_unicode: bool,
+
+	#[arg(long)]
+	pub pair_live_photos: bool,
+
+	#[arg(long, value_enum, default_value_t = LiveVideo::Pictures)]
+	pub live_video: LiveVideo,
+
+	#[arg(long)]
+	pub apple_edited: bool,
+
+	#[arg(long, requires = "apple_edited")]
+	pub apple_originals: bool,
+
+	#[arg(long)]
+	pub group_by_camera: bool,
+
+	#[arg(long)]
+	pub group_by_aspect: bool,
+
+	#[arg(long, value_enum)]
+	pub sort_videos_by: Option<VideoSort>,
+
+	#[arg(long, default_value_t = 60)]
+	pub duration_threshold: u64,
+
+	#[arg(long)]
+	pub group_bursts: bool,
+
+	#[arg(long, default_value_t = 2)]
+	pub burst_window: u32,
+
+	#[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(2..))]
+	pub burst_min: u64,
+
+	#[arg(long)]
+	pub geo: bool,
+
+	#[arg(long, default_value_t = 1.0, value_parser = parse_grid)]
+	pub geo_grid: f64,
+
+	#[arg(long)]
+	pub simulate_tree: bool,
+
+	#[arg(long)]
+	pub stats: bool,
+
+	#[arg(long)]
+	pub stats_only: bool,
+
+	#[arg(long, conflicts_with = "archive")]
+	pub against: Option<PathBuf>,
+
+	#[arg(long)]
+	pub move_empty_dirs: bool,
+
+	#[arg(long, default_value_t = 3)]
+	pub retries: u32,
+
+	#[arg(long, default_value_t = 4)]
+	pub threads: usize,
+
+	#[arg(long)]
+	pub max_files: Option<usize>,
+
+	#[arg(long, conflicts_with = "archive", value_parser = clap::value_parser!(u64).range(1..))]
+	pub max_per_dir: Option<u64>,
+
+	#[arg(long)]
+	pub strict: bool,
+
+	#[arg(long)]
+	pub skip_unreadable: bool,
+
+	#[arg(long, default_value = "leave", value_parser = parse_on_skip)]
+	pub on_skip: OnSkip,
+
+	#[arg(long)]
+	pub verify: bool,
+
+	#[arg(long)]
+	pub dest_mtime: bool,
+
+	#[arg(long, value_parser = parse_mode)]
+	pub dir_mode: Option<u32>,
+
+	#[arg(long, value_parser = parse_mode, conflicts_with = "hardlink")]
+	pub file_mode: Option<u32>,
+
+	#[arg(long)]
+	pub min_depth: Option<usize>,
+
+	#[arg(long)]
+	pub max_depth: Option<usize>,
+}
+
+fn parse_date(date: &str) -> std::result::Result<NaiveDate, String> {
+	NaiveDate::parse_from_str(date, "%Y-%m-%d")
+		.map_err(|_| format!("Expected YYYY-MM-DD, got {date}"))
+}
+
+fn parse_person(person: &str) -> std::result::Result<String, String> {
+	let components: Vec<_> = person
+		.split(['/', '\\'])
+		.filter(|component| !component.is_empty() && *component != ".")
+		.collect();
+
+	match components.as_slice() {
+		[] => Err(format!("Invalid person {person}")),
+		components if components.contains(&"..") => {
+			Err(format!("Person {person} must not contain .."))
+		}
+		components if components.iter().any(|component| component.contains(':')) => {
+			Err(format!("Person {person} must not contain :"))
+		}
+		components => Ok(components.join("/")),
+	}
+}
+
+fn parse_grid(grid: &str) -> std::result::Result<f64, String> {
+	match grid.trim().parse::<f64>() {
+		Ok(grid) if grid.is_finite() && grid > 0.0 => Ok(grid),
+		_ => Err(format!(
+			"Invalid grid size {grid}, expected a positive number of degrees"
+		)),
+	}
+}
+
+fn parse_mode(mode: &str) -> std::result::Result<u32, String> {
+	match u32::from_str_radix(mode.trim().trim_start_matches("0o"), 8) {
+		Ok(mode) if mode <= 0o7777 => Ok(mode),
+		_ => Err(format!(
+			"Invalid mode {mode}, expected octal permissions like 775"
+		)),
+	}
+}
+
+fn parse_size(size: &str) -> std::result::Result<u64, String> {
+	let size = size.trim();
+	let split = size
+		.find(|c: char| !c.is_ascii_digit() && c != '.')
+		.unwrap_or(size.len());
+	let (number, unit) = size.split_at(split);
+	let number: f64 = number.parse().map_err(|_| format!("Invalid size {size}"))?;
+
+	let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+		"" | "b" => 1,
+		"k" | "kb" => 1_000,
+		"kib" => 1 << 10,
+		"m" | "mb" => 1_000_000,
+		"mib" => 1 << 20,
+		"g" | "gb" => 1_000_000_000,
+		"gib" => 1 << 30,
+		"t" | "tb" => 1_000_000_000_000,
+		"tib" => 1 << 40,
+		unit => return Err(format!("Unknown size unit {unit}")),
+	};
+
+	Ok((number * multiplier as f64) as u64)
+}
+
+fn parse_extension(extension: &str) -> std::result::Result<String, String> {
+	match extension.trim().trim_start_matches('.') {
+		"" => Err(format!("Invalid extension {extension}")),
+		extension => Ok(extension.to_lowercase()),
+	}
+}
+
+fn parse_mime(mime: &str) -> std::result::Result<String, String> {
+	match mime.trim().split_once('/') {
+		Some((kind, subtype)) if !kind.is_empty() && !subtype.is_empty() => {
+			Ok(mime.trim().to_lowercase())
+		}
+		_ => Err(format!("Invalid MIME type {mime}")),
+	}
+}
+
+fn parse_on_skip(on_skip: &str) -> std::result::Result<OnSkip, String> {
+	match on_skip.split_once(':') {
+		_ if on_skip == "leave" => Ok(OnSkip::Leave),
+		Some(("move-to", dir)) if !dir.is_empty() => Ok(OnSkip::MoveTo(PathBuf::from(dir))),
+		_ => Err(format!(
+			"Invalid value {on_skip}, expected leave or move-to:<dir>"
+		)),
+	}
+}
+
+pub fn parse_map(map: &str) -> std::result::Result<(String, Extension), String> {
+	let (extension, category) = map
+		.split_once('=')
+		.ok_or(format!("Expected EXT=CATEGORY, got {map}"))?;
+	let category = Extension::from_str(category, true)
+		.map_err(|_| format!("Unknown category {category}, expected one of image, video, audio"))?;
+
+	Ok((extension.to_lowercase(), category))
+}
+
+impl Config {
+	fn kind_dir(&self, extension: &Extension) -> String {
+		let dir = match extension {
+			Extension::Image => &self.image_dir,
+			Extension::Video => &self.video_dir,
+			Extension::Audio => &self.audio_dir,
+		};
+
+		dir.clone().unwrap_or_else(|| extension.to_string())
+	}
+}
+
+impl AsRef<Path> for Config {
+	fn as_ref(&self) -> &Path {
+		&self.destination
+	}
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Conflict {
+	Skip,
+	Rename,
+	Newest,
+	Largest,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LiveVideo {
+	Pictures,
+	Videos,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Symlink {
+	Skip,
+	Follow,
+	Move,
+}
+
+#[derive(Clone)]
+pub enum OnSkip {
+	Leave,
+	MoveTo(PathBuf),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum VideoSort {
+	Duration,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PersonFrom {
+	Dir,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DestStructure {
+	#[value(name = "year/month")]
+	YearMonth,
+	#[value(name = "year-month")]
+	YearDashMonth,
+	#[value(name = "month/year")]
+	MonthYear,
+	#[value(name = "year")]
+	Year,
+	#[value(name = "year-prefixed")]
+	YearPrefixed,
+}
+
+impl From<DestStructure> for Template {
+	fn from(structure: DestStructure) -> Self {
+		let template = match structure {
+			DestStructure::YearMonth => "{kind}/{person}/{year}/{month}",
+			DestStructure::YearDashMonth => "{kind}/{person}/{year}-{month_number}",
+			DestStructure::MonthYear => "{kind}/{person}/{month}/{year}",
+			DestStructure::Year | DestStructure::YearPrefixed => "{kind}/{person}/{year}",
+		};
+
+		Template::parse(template).unwrap()
+	}
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+	#[default]
+	Human,
+	Json,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Color {
+	#[default]
+	Auto,
+	Always,
+	Never,
+}
+
+impl Color {
+	pub fn apply(self) {
+		let enabled = match self {
+			Color::Always => true,
+			Color::Never => false,
+			Color::Auto => {
+				std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+					&& io::stdout().is_terminal()
+					&& io::stderr().is_terminal()
+			}
+		};
+
+		colored::control::set_override(enabled);
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Capability {
+	Exif,
+	Ffprobe,
+	Filesystem,
+}
+
+impl Display for Capability {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Capability::Exif => write!(f, "exif"),
+			Capability::Ffprobe => write!(f, "ffprobe"),
+			Capability::Filesystem => write!(f, "filesystem"),
+		}
+	}
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Archive {
+	Zip,
+	Tar,
+}
+
+impl Display for Archive {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Archive::Zip => write!(f, "zip"),
+			Archive::Tar => write!(f, "tar"),
+		}
+	}
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DateFrom {
+	Exif,
+	Filename,
+	Mtime,
+}
+
+#[derive(Clone, Copy)]
+pub enum DateSource {
+	Exif,
+	Filename,
+	Mtime,
+}
+
+impl Display for DateSource {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			DateSource::Exif => write!(f, "exif"),
+			DateSource::Filename => write!(f, "filename"),
+			DateSource::Mtime => write!(f, "mtime"),
+		}
+	}
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MonthFormat {
+	Name,
+	Number,
+	NameNumber,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Mode {
+	Move,
+	Copy,
+	Hardlink,
+}
+
+impl From<&Config> for Mode {
+	fn from(config: &Config) -> Self {
+		match (config.copy, config.hardlink, config.mode) {
+			(true, _, _) => Mode::Copy,
+			(_, true, _) => Mode::Hardlink,
+			(_, _, Some(mode)) => mode,
+			_ => Mode::Move,
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct Target {
+	pub abs_path: PathBuf,
+	pub extension: Extension,
+	pub mtime: MTime,
+	pub name: OsString,
+	pub size: u64,
+	pub modified: i64,
+	pub link: bool,
+	pub gps: Option<(f64, f64)>,
+	pub camera: Option<String>,
+	pub aspect: Option<&'static str>,
+	pub artist: Option<String>,
+	pub burst: Option<String>,
+	pub duration: Option<Duration>,
+	pub apple_original: bool,
+}
+
+fn sanitize(text: &str) -> String {
+	text.chars()
+		.map(|c| match c {
+			'/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>()
+		.trim()
+		.chars()
+		.scan(true, |leading, c| {
+			*leading = *leading && c == '.';
+			Some(match leading {
+				true => '_',
+				false => c,
+			})
+		})
+		.collect()
+}
+
+fn arrow(from: &Path, to: &Path) -> String {
+	format!("{} -> {}", from.display(), to.display())
+		.green()
+		.to_string()
+}
+
+fn path_error(err: io::Error, path: &Path) -> Error {
+	match err.kind() {
+		io::ErrorKind::NotFound => Error::Vanished(path.to_path_buf()),
+		io::ErrorKind::PermissionDenied => Error::Permission(path.to_path_buf()),
+		_ => Error::Io(err),
+	}
+}
+
+impl TryFrom<(&Path, &Config, &dyn FileSystem)> for Target {
+	type Error = Error;
+
+	fn try_from((path, config, fs): (&Path, &Config, &dyn FileSystem)) -> Result<Self> {
+		let metadata = fs.metadata(path).map_err(|err| path_error(err, path))?;
+		Self::try_from((path, metadata, config, fs))
+	}
+}
+
+impl TryFrom<(&DirEntry, &Config, &dyn FileSystem)> for Target {
+	type Error = Error;
+
+	fn try_from((entry, config, fs): (&DirEntry, &Config, &dyn FileSystem)) -> Result<Self> {
+		let metadata = fs
+			.entry_metadata(entry)
+			.map_err(|err| path_error(err, entry.path()))?;
+		Self::try_from((entry.path(), metadata, config, fs))
+	}
+}
+
+impl TryFrom<(&Path, Metadata, &Config, &dyn FileSystem)> for Target {
+	type Error = Error;
+
+	fn try_from(
+		(path, metadata, config, fs): (&Path, Metadata, &Config, &dyn FileSystem),
+	) -> Result<Self> {
+		if metadata.is_dir {
+			return Err(Error::Dir(path.to_path_buf()));
+		}
+
+		let link = fs.is_symlink(path) && matches!(config.symlink, Symlink::Move);
+		let abs_path = match (link, path.parent(), path.file_name()) {
+			(true, Some(parent), Some(name)) => fs
+				.canonicalize(parent)
+				.map_err(|err| path_error(err, path))?
+				.join(name),
+			_ => fs.canonicalize(path).map_err(|err| path_error(err, path))?,
+		};
+		let extension = Extension::try_from((&abs_path, config, fs))?;
+		let name = abs_path
+			.file_name()
+			.ok_or(Error::NoName(path.to_path_buf()))?
+			.to_os_string();
+		let exif = match (config.date_from, &extension) {
+			(DateFrom::Exif | DateFrom::Filename, Extension::Image) => {
+				MTime::from_exif(&abs_path, config, fs).ok()
+			}
+			_ => None,
+		};
+		let mtime = match (exif, config.date_from) {
+			(Some(mtime), _) => mtime,
+			(None, DateFrom::Filename) => MTime::from_filename(&abs_path, config)
+				.or_else(|_| MTime::from_mtime(&abs_path, metadata.modified, config))?,
+			(None, _) => MTime::from_mtime(&abs_path, metadata.modified, config)?,
+		};
+		let gps = match (config.geo, &extension) {
+			(true, Extension::Image) => Exif::read(fs, &abs_path).and_then(|exif| exif.gps()),
+			_ => None,
+		};
+		let camera = match (config.group_by_camera, &extension) {
+			(true, Extension::Image) => Exif::read(fs, &abs_path)
+				.and_then(|exif| exif.camera())
+				.map(|camera| sanitize(&camera))
+				.filter(|camera| !camera.is_empty()),
+			_ => None,
+		};
+		let aspect = match (config.group_by_aspect, &extension) {
+			(true, Extension::Image) => aspect::aspect(fs, &abs_path),
+			_ => None,
+		};
+		let artist = match (config.owner_from_exif_artist, &extension) {
+			(true, Extension::Image) => Exif::read(fs, &abs_path)
+				.and_then(|exif| exif.artist())
+				.and_then(|artist| parse_person(&sanitize(&artist)).ok()),
+			_ => None,
+		};
+		let duration = match (config.sort_videos_by, &extension) {
+			(Some(VideoSort::Duration), Extension::Video) => duration::duration(fs, &abs_path),
+			_ => None,
+		};
+
+		Ok(Self {
+			abs_path,
+			name,
+			extension,
+			mtime,
+			size: metadata.len,
+			modified: metadata.modified,
+			link,
+			gps,
+			camera,
+			aspect,
+			artist,
+			burst: None,
+			duration,
+			apple_original: false,
+		})
+	}
+}
+
+#[derive(Clone)]
+pub struct MTime {
+	pub date: NaiveDateTime,
+	pub year: String,
+	pub month: String,
+	pub month_number: String,
+	pub day: String,
+	pub source: DateSource,
+}
+
+impl MTime {
+	fn new(date: NaiveDateTime, source: DateSource, config: &Config) -> Self {
+		let day = match config.same_day_threshold {
+			Some(hour) if date.hour() < hour => date.date().pred_opt().unwrap_or(date.date()),
+			_ => date.date(),
+		};
+
+		let name = config.locale.month(day.month0()).to_string();
+		let month_number = format!("{:02}", day.month());
+		let month = match config.month_format {
+			MonthFormat::Name => name,
+			MonthFormat::Number => month_number.clone(),
+			MonthFormat::NameNumber => format!("{month_number}-{name}"),
+		};
+		let year = day.year().to_string();
+		let day = format!("{:02}", day.day());
+
+		Self { date, year, month, month_number, day, source }
+	}
+
+	fn timestamp(&self, timezone: &Timezone) -> i64 {
+		timezone.timestamp(&self.date)
+	}
+
+	fn from_mtime(path: &Path, secs: i64, config: &Config) -> Result<Self> {
+		let date = DateTime::from_timestamp(secs, 0).ok_or(Error::DateTime(path.to_path_buf()))?;
+
+		Ok(Self::new(
+			config.timezone.localize(date),
+			DateSource::Mtime,
+			config,
+		))
+	}
+
+	fn from_exif(path: &Path, config: &Config, fs: &dyn FileSystem) -> Result<Self> {
+		let date = Exif::read(fs, path)
+			.and_then(|exif| exif.date_time_original())
+			.ok_or(Error::Exif(path.to_path_buf()))?;
+
+		Ok(Self::new(date, DateSource::Exif, config))
+	}
+
+	fn from_filename(path: &Path, config: &Config) -> Result<Self> {
+		let date = filename::date(path, &config.filename_pattern)
+			.ok_or(Error::FilenameDate(path.to_path_buf()))?;
+
+		Ok(Self::new(date, DateSource::Filename, config))
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Extension {
+	Image,
+	Video,
+	Audio,
+}
+
+impl TryFrom<(&PathBuf, &Config, &dyn FileSystem)> for Extension {
+	type Error = Error;
+
+	fn try_from((path, config, fs): (&PathBuf, &Config, &dyn FileSystem)) -> Result<Self> {
+		let Some(extension) = path.extension() else {
+			return match config.only_ext.is_empty() {
+				true => sniff(fs, path).ok_or(Error::Skipping(path.clone())),
+				false => Err(Error::Skipping(path.clone())),
+			};
+		};
+
+		let extension = extension
+			.to_str()
+			.ok_or(Error::Skipping(path.clone()))?
+			.to_lowercase();
+
+		if !config.only_ext.is_empty() && !config.only_ext.contains(&extension) {
+			return Err(Error::Skipping(path.clone()));
+		}
+
+		if let Some(category) = config.overrides.get(&extension) {
+			return Ok(category.clone());
+		}
+
+		if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+			return Ok(Extension::Image);
+		}
+
+		if !config.only_ext.is_empty() {
+			return sniff(fs, path).ok_or(Error::Skipping(path.clone()));
+		}
+
+		let mime = mime_guess::from_ext(&extension)
+			.first()
+			.ok_or(Error::Mime(path.clone()))?
+			.to_string();
+
+		let excluded =
+			config
+				.exclude_mime
+				.iter()
+				.any(|excluded| match excluded.strip_suffix("/*") {
+					Some(kind) => mime.split('/').next() == Some(kind),
+					None => *excluded == mime,
+				});
+
+		if excluded {
+			return Err(Error::Skipping(path.clone()));
+		}
+
+		let extension = match mime {
+			ext if ext.starts_with("image") => Extension::Image,
+			ext if ext.starts_with("video") => Extension::Video,
+			ext if ext.starts_with("audio") => Extension::Audio,
+			_ => return Err(Error::Skipping(path.clone())),
+		};
+
+		Ok(extension)
+	}
+}
+
+impl Display for Extension {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Extension::Video => write!(f, "videos"),
+			Extension::Image => write!(f, "pictures"),
+			Extension::Audio => write!(f, "audio"),
+		}
+	}
+}
+
+fn retry<T>(retries: u32, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+	let mut attempt = 0;
+
+	loop {
+		match operation() {
+			Err(err) if attempt < retries && is_transient(&err) => {
+				let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+				std::thread::sleep(RETRY_BACKOFF.saturating_mul(factor).min(MAX_RETRY_BACKOFF));
+				attempt += 1;
+			}
+			result => return result,
+		}
+	}
+}
+
+fn is_transient(err: &io::Error) -> bool {
+	matches!(
+		err.kind(),
+		io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+	)
+}
+
+fn rename(fs: &dyn FileSystem, from: &Path, to: &Path, retries: u32) -> Result<()> {
+	match retry(retries, || fs.rename(from, to)) {
+		Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+			if copy(fs, from, to, retries, None).is_err() {
+				return Err(Error::CrossDevice(from.to_path_buf()));
+			}
+
+			fs.remove_file(from)?;
+			Ok(())
+		}
+		result => Ok(result?),
+	}
+}
+
+fn lowercase_extension(name: &OsStr) -> OsString {
+	let path = Path::new(name);
+
+	match path.extension().and_then(OsStr::to_str) {
+		Some(extension) => path
+			.with_extension(extension.to_lowercase())
+			.into_os_string(),
+		None => name.to_os_string(),
+	}
+}
+
+fn split_name(name: &OsStr) -> (OsString, OsString) {
+	if let Some(name) = name.to_str() {
+		let (stem, extension) = match name.char_indices().skip(1).find(|(_, c)| *c == '.') {
+			Some((idx, _)) => name.split_at(idx),
+			None => (name, ""),
+		};
+
+		return (stem.into(), extension.into());
+	}
+
+	let path = Path::new(name);
+	let stem = path.file_stem().unwrap_or(name).to_os_string();
+	let mut extension = OsString::new();
+
+	if let Some(suffix) = path.extension() {
+		extension.push(".");
+		extension.push(suffix);
+	}
+
+	(stem, extension)
+}
+
+fn unique_destination(dir: &Path, name: &OsStr, taken: impl Fn(&Path) -> bool) -> PathBuf {
+	let (stem, extension) = split_name(name);
+
+	(1..)
+		.map(|n| {
+			let mut name = stem.clone();
+			name.push(format!("_{n}"));
+			name.push(&extension);
+			dir.join(name)
+		})
+		.find(|path| !taken(path))
+		.unwrap()
+}
+
+fn fold_case(path: &Path) -> PathBuf {
+	path.to_str().map_or(path.to_path_buf(), |path| {
+		PathBuf::from(path.to_lowercase())
+	})
+}
+
+fn is_case_insensitive(fs: &dyn FileSystem, dir: &Path) -> bool {
+	let Some(name) = dir.file_name().and_then(OsStr::to_str) else {
+		return false;
+	};
+
+	let swapped: String = name
+		.chars()
+		.map(|c| match c.is_lowercase() {
+			true => c.to_ascii_uppercase(),
+			false => c.to_ascii_lowercase(),
+		})
+		.collect();
+
+	swapped != name && fs.exists(&dir.with_file_name(swapped))
+}
+
+fn create_dir(fs: &dyn FileSystem, dir: &Path, mode: Option<u32>) -> Result<()> {
+	let created: Vec<_> = match mode {
+		Some(_) => dir.ancestors().take_while(|dir| !fs.exists(dir)).collect(),
+		None => Vec::new(),
+	};
+
+	match fs.create_dir_all(dir) {
+		Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err.into()),
+		_ => {}
+	}
+
+	if let Some(mode) = mode {
+		for dir in created {
+			fs.set_permissions(dir, mode)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn hash_file(fs: &dyn FileSystem, path: &Path, progress: &Progress) -> Result<Hash> {
+	let len = fs.metadata(path)?.len;
+	let progress = |hashed| progress.hashing(path, hashed, len);
+
+	Ok(hash::hash_reader_with(fs.open(path)?, progress)?)
+}
+
+fn same_contents(
+	fs: &dyn FileSystem,
+	from: &Path,
+	to: &Path,
+	hash: impl Fn(&Path) -> Result<Hash>,
+) -> Result<bool> {
+	Ok(fs.metadata(from)?.len == fs.metadata(to)?.len
+		&& hash::hash_prefix(fs.open(from)?)? == hash::hash_prefix(fs.open(to)?)?
+		&& hash(from)? == hash(to)?)
+}
+
+fn temp_path(to: &Path) -> PathBuf {
+	let id = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+	to.with_file_name(format!(".tf-tmp-{}-{id}", std::process::id()))
+}
+
+fn copy(
+	fs: &dyn FileSystem,
+	from: &Path,
+	to: &Path,
+	retries: u32,
+	verify: Option<&Progress>,
+) -> Result<()> {
+	let temp = temp_path(to);
+
+	let copied = retry(retries, || fs.copy(from, &temp))
+		.map_err(Error::from)
+		.and_then(|_| match verify {
+			Some(progress)
+				if !same_contents(fs, from, &temp, |path| hash_file(fs, path, progress))? =>
+			{
+				Err(Error::Verify(to.to_path_buf()))
+			}
+			_ => Ok(()),
+		})
+		.and_then(|_| Ok(fs.rename(&temp, to)?));
+
+	if copied.is_err() {
+		let _ = fs.remove_file(&temp);
+	}
+
+	copied
+}
+
+fn place_link(fs: &dyn FileSystem, mode: Mode, from: &Path, to: &Path) -> Result<()> {
+	if fs.exists(to) || fs.is_symlink(to) {
+		return Err(Error::DestinationExists(to.to_path_buf()));
+	}
+
+	let pointee = fs.read_link(from)?;
+	let pointee = match (pointee.is_relative(), from.parent()) {
+		(true, Some(parent)) => parent.join(pointee),
+		_ => pointee,
+	};
+
+	fs.symlink(&pointee, to)?;
+
+	if let Mode::Move = mode {
+		fs.remove_file(from)?;
+	}
+
+	Ok(())
+}
+
+fn place(
+	fs: &dyn FileSystem,
+	mode: Mode,
+	from: &Path,
+	to: &Path,
+	retries: u32,
+	verify: Option<&Progress>,
+) -> Result<()> {
+	if fs.exists(to) {
+		return Err(Error::DestinationExists(to.to_path_buf()));
+	}
+
+	match mode {
+		Mode::Move => rename(fs, from, to, retries),
+		Mode::Copy => copy(fs, from, to, retries, verify),
+		Mode::Hardlink => match retry(retries, || fs.hard_link(from, to)) {
+			Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+				Err(Error::HardlinkCrossDevice(from.to_path_buf()))
+			}
+			result => Ok(result?),
+		},
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Level {
+	Error,
+	Warn,
+	Info,
+	Debug,
+}
+
+impl Level {
+	fn new(verbose: bool) -> Self {
+		let env = std::env::var("RUST_LOG").unwrap_or_default();
+
+		match env.to_lowercase().as_str() {
+			"error" => Level::Error,
+			"warn" => Level::Warn,
+			"info" => Level::Info,
+			"debug" | "trace" => Level::Debug,
+			_ if verbose => Level::Debug,
+			_ => Level::Info,
+		}
+	}
+}
+
+struct Output {
+	level: Level,
+	format: Format,
+	stdout: Vec<String>,
+	stderr: Vec<String>,
+	planned: Vec<(PathBuf, PathBuf)>,
+	record: OperationRecord,
+}
+
+impl Output {
+	fn new(level: Level, format: Format) -> Self {
+		Self {
+			level,
+			format,
+			stdout: Vec::new(),
+			stderr: Vec::new(),
+			planned: Vec::new(),
+			record: OperationRecord::default(),
+		}
+	}
+
+	fn log(&mut self, level: Level, message: String) {
+		if level > self.level {
+			return;
+		}
+
+		if let Format::Json = self.format {
+			let level = match level {
+				Level::Error => "error",
+				Level::Warn => "warning",
+				Level::Info | Level::Debug => return,
+			};
+			let line = Value::object([
+				("level", Value::from(level.to_string())),
+				("message", Value::from(message)),
+			]);
+
+			self.stderr.push(line.to_string());
+			return;
+		}
+
+		match level {
+			Level::Error => self
+				.stderr
+				.push(format!("Error: {message}").red().to_string()),
+			Level::Warn => self
+				.stderr
+				.push(format!("Warning: {message}").yellow().to_string()),
+			Level::Info | Level::Debug => self.stdout.push(message),
+		}
+	}
+
+	fn plan(&mut self, from: &Path, to: &Path) {
+		if let (Format::Human, true) = (self.format, self.level >= Level::Info) {
+			self.planned.push((from.to_path_buf(), to.to_path_buf()));
+		}
+	}
+
+	fn flush(self) {
+		if !self.stdout.is_empty() {
+			let mut stdout = io::stdout().lock();
+			let _ = writeln!(stdout, "{}", self.stdout.join("\n"));
+		}
+
+		if !self.stderr.is_empty() {
+			let mut stderr = io::stderr().lock();
+			let _ = writeln!(stderr, "{}", self.stderr.join("\n"));
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+	Moved,
+	Copied,
+	Linked,
+	Present,
+	Skipped,
+	Failed,
+}
+
+impl Display for Action {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Action::Moved => write!(f, "moved"),
+			Action::Copied => write!(f, "copied"),
+			Action::Linked => write!(f, "linked"),
+			Action::Present => write!(f, "present"),
+			Action::Skipped => write!(f, "skipped"),
+			Action::Failed => write!(f, "failed"),
+		}
+	}
+}
+
+impl From<Mode> for Action {
+	fn from(mode: Mode) -> Self {
+		match mode {
+			Mode::Move => Action::Moved,
+			Mode::Copy => Action::Copied,
+			Mode::Hardlink => Action::Linked,
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct Summary {
+	moved: AtomicUsize,
+	copied: AtomicUsize,
+	linked: AtomicUsize,
+	present: AtomicUsize,
+	skipped: AtomicUsize,
+	failed: AtomicUsize,
+}
+
+impl Summary {
+	pub fn moved(&self) -> usize {
+		self.moved.load(Ordering::Relaxed)
+	}
+
+	pub fn copied(&self) -> usize {
+		self.copied.load(Ordering::Relaxed)
+	}
+
+	pub fn linked(&self) -> usize {
+		self.linked.load(Ordering::Relaxed)
+	}
+
+	pub fn present(&self) -> usize {
+		self.present.load(Ordering::Relaxed)
+	}
+
+	pub fn skipped(&self) -> usize {
+		self.skipped.load(Ordering::Relaxed)
+	}
+
+	pub fn failed(&self) -> usize {
+		self.failed.load(Ordering::Relaxed)
+	}
+
+	fn record(&self, action: Action) {
+		let counter = match action {
+			Action::Moved => &self.moved,
+			Action::Copied => &self.copied,
+			Action::Linked => &self.linked,
+			Action::Present => &self.present,
+			Action::Skipped => &self.skipped,
+			Action::Failed => &self.failed,
+		};
+
+		counter.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+impl Display for Summary {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(
+			f,
+			"moved: {}, copied: {}, linked: {}, already imported: {}, skipped: {}, failed: {}",
+			self.moved(),
+			self.copied(),
+			self.linked(),
+			self.present(),
+			self.skipped(),
+			self.failed()
+		)
+	}
+}
+
+impl From<&Summary> for Value {
+	fn from(summary: &Summary) -> Self {
+		Value::object([
+			("moved", Value::from(summary.moved())),
+			("copied", Value::from(summary.copied())),
+			("linked", Value::from(summary.linked())),
+			("present", Value::from(summary.present())),
+			("skipped", Value::from(summary.skipped())),
+			("failed", Value::from(summary.failed())),
+		])
+	}
+}
+
+#[derive(Default)]
+struct OperationRecord {
+	source: PathBuf,
+	destination: Option<PathBuf>,
+	action: Option<Action>,
+	reason: Option<String>,
+}
+
+impl From<&OperationRecord> for Value {
+	fn from(record: &OperationRecord) -> Self {
+		Value::object([
+			("source", Value::from(record.source.as_path())),
+			("destination", Value::from(record.destination.as_deref())),
+			(
+				"action",
+				Value::from(record.action.map(|action| action.to_string())),
+			),
+			("reason", Value::from(record.reason.clone())),
+		])
+	}
+}
+
+struct Progress {
+	total: usize,
+	done: Mutex<usize>,
+	visible: bool,
+}
+
+impl Progress {
+	fn new(total: usize) -> Self {
+		Self {
+			total,
+			done: Mutex::default(),
+			visible: io::stderr().is_terminal(),
+		}
+	}
+
+	fn finish(&self, output: Output) {
+		let mut done = self.done.lock().unwrap();
+		*done += 1;
+
+		self.clear();
+		output.flush();
+
+		if self.visible {
+			eprint!("[{}/{}]", done, self.total);
+		}
+	}
+
+	fn hashing(&self, path: &Path, hashed: u64, total: u64) {
+		if !self.visible || total <= hash::CHUNK as u64 {
+			return;
+		}
+
+		let done = self.done.lock().unwrap();
+		let name = path.file_name().unwrap_or_default().to_string_lossy();
+		eprint!(
+			"\r\x1b[2K[{}/{}] hashing {name} {}%",
+			done,
+			self.total,
+			hashed * 100 / total
+		);
+	}
+
+	fn clear(&self) {
+		if self.visible {
+			eprint!("\r\x1b[2K");
+		}
+	}
+}
+
+type PendingArchive<'a> = (archive::Writer<'a>, Vec<(PathBuf, PathBuf)>);
+
+struct Context<'a> {
+	config: &'a Config,
+	fs: &'a dyn FileSystem,
+	mode: Mode,
+	claimed: Mutex<HashSet<PathBuf>>,
+	case_insensitive: bool,
+	hashes: Mutex<HashMap<Hash, PathBuf>>,
+	dedup_candidates: HashSet<PathBuf>,
+	checksums: Option<ChecksumDb>,
+	records: Mutex<Vec<OperationRecord>>,
+	level: Level,
+	journal: Option<Mutex<Box<dyn Write + Send + 'a>>>,
+	archives: Mutex<HashMap<PathBuf, PendingArchive<'a>>>,
+	processed: HashMap<(PathBuf, u64, i64), PathBuf>,
+	live_videos: HashSet<PathBuf>,
+	paired: Mutex<HashSet<PathBuf>>,
+	apple_edited: HashMap<PathBuf, MTime>,
+	apple_originals: HashSet<PathBuf>,
+	bursts: HashMap<PathBuf, String>,
+	tree: Mutex<Tree>,
+	stats: Mutex<Stats>,
+	progress: Progress,
+	diff: Mutex<Diff>,
+	parts: Mutex<HashMap<PathBuf, Vec<usize>>>,
+	sequences: Mutex<HashMap<PathBuf, usize>>,
+	plan: Mutex<Plan>,
+}
+
+impl<'a> Context<'a> {
+	fn new(config: &'a Config, fs: &'a dyn FileSystem) -> Result<Self> {
+		let journal = match &config.journal {
+			Some(path) if !config.dry_run => Some(Mutex::new(fs.append(path)?)),
+			_ => None,
+		};
+
+		let checksums = match &config.checksum_db {
+			Some(path) => Some(ChecksumDb::load(path, fs)?),
+			None => None,
+		};
+
+		let processed = match (&config.journal, config.resume) {
+			(Some(path), true) => processed(path, fs)?,
+			_ => HashMap::new(),
+		};
+
+		Ok(Self {
+			config,
+			fs,
+			mode: Mode::from(config),
+			claimed: Mutex::default(),
+			case_insensitive: config.case_insensitive
+				|| is_case_insensitive(fs, &config.destination),
+			hashes: Mutex::default(),
+			dedup_candidates: HashSet::new(),
+			checksums,
+			records: Mutex::default(),
+			level: Level::new(config.verbose),
+			journal,
+			archives: Mutex::default(),
+			processed,
+			live_videos: HashSet::new(),
+			paired: Mutex::default(),
+			apple_edited: HashMap::new(),
+			apple_originals: HashSet::new(),
+			bursts: HashMap::new(),
+			tree: Mutex::new(Tree::new(&config.destination)),
+			stats: Mutex::default(),
+			progress: Progress::new(0),
+			diff: Mutex::default(),
+			parts: Mutex::default(),
+			sequences: Mutex::default(),
+			plan: Mutex::default(),
+		})
+	}
+
+	fn journal(&self, from: &Path, to: &Path) -> io::Result<()> {
+		let Some(journal) = &self.journal else {
+			return Ok(());
+		};
+
+		let time = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+		let metadata = self.fs.metadata(from).or_else(|_| self.fs.metadata(to))?;
+		let entry = Value::object([
+			("from", Value::from(from)),
+			("to", Value::from(to)),
+			("action", Value::from(Action::from(self.mode).to_string())),
+			("time", Value::from(time)),
+			("size", Value::from(metadata.len)),
+			("modified", Value::from(metadata.modified)),
+		]);
+
+		let mut journal = journal.lock().unwrap();
+		writeln!(journal, "{entry}")?;
+		journal.flush()
+	}
+
+	fn processed(&self, path: &Path) -> Option<&PathBuf> {
+		let path = self.fs.canonicalize(path).ok()?;
+		let metadata = self.fs.metadata(&path).ok()?;
+		self.processed.get(&(path, metadata.len, metadata.modified))
+	}
+
+	fn set_file_mode(&self, path: &Path) -> io::Result<()> {
+		match self.config.file_mode {
+			Some(mode) => self.fs.set_permissions(path, mode),
+			None => Ok(()),
+		}
+	}
+
+	fn hash(&self, path: &Path) -> Result<Hash> {
+		let Some(checksums) = &self.checksums else {
+			return hash_file(self.fs, path, &self.progress);
+		};
+
+		let len = self.fs.metadata(path)?.len;
+		let progress = |hashed| self.progress.hashing(path, hashed, len);
+		Ok(checksums.hash(self.fs, path, progress)?)
+	}
+
+	fn is_imported(&self, from: &Path, to: &Path) -> Result<bool> {
+		same_contents(self.fs, from, to, |path| self.hash(path))
+	}
+
+	fn duplicate_of(&self, entry: &DirEntry, target: &Target) -> Result<Option<PathBuf>> {
+		if !self.dedup_candidates.contains(entry.path()) {
+			return Ok(None);
+		}
+
+		let hash = self.hash(&target.abs_path)?;
+		let mut hashes = self.hashes.lock().unwrap();
+
+		match hashes.get(&hash) {
+			Some(original) => Ok(Some(original.clone())),
+			None => {
+				hashes.insert(hash, target.abs_path.clone());
+				Ok(None)
+			}
+		}
+	}
+
+	fn claim_key(&self, path: &Path) -> PathBuf {
+		match self.case_insensitive {
+			true => fold_case(path),
+			false => path.to_path_buf(),
+		}
+	}
+
+	fn claim(&self, dir: &Path, name: &OsStr) -> Option<PathBuf> {
+		let mut claimed = self.claimed.lock().unwrap();
+		let is_taken =
+			|path: &Path| self.fs.exists(path) || claimed.contains(&self.claim_key(path));
+		let mut dest_file = dir.join(name);
+		let taken = is_taken(&dest_file);
+
+		let conflict = match (&self.config.rename_pattern, &self.config.rename_on_move) {
+			(None, None) => self.config.on_conflict,
+			_ => Conflict::Rename,
+		};
+
+		match (taken, conflict) {
+			(false, _) => {}
+			(true, Conflict::Rename) => dest_file = unique_destination(dir, name, is_taken),
+			(true, Conflict::Newest | Conflict::Largest)
+				if !claimed.contains(&self.claim_key(&dest_file)) => {}
+			(true, _) => return None,
+		}
+
+		claimed.insert(self.claim_key(&dest_file));
+		Some(dest_file)
+	}
+
+	fn sequence(&self, dir: &Path) -> usize {
+		let mut sequences = self.sequences.lock().unwrap();
+		let seq = sequences
+			.entry(dir.to_path_buf())
+			.or_insert_with(|| file_count(self.fs, dir).unwrap_or(0));
+
+		*seq += 1;
+		*seq
+	}
+
+	fn part(&self, dir: &Path, name: &OsStr, max: usize) -> PathBuf {
+		let part_dir = |idx: usize| match idx {
+			0 => dir.to_path_buf(),
+			idx => dir.join(format!("part{idx:02}")),
+		};
+
+		let mut parts = self.parts.lock().unwrap();
+		let counts = parts.entry(dir.to_path_buf()).or_insert_with(|| {
+			(0..)
+				.map(part_dir)
+				.map_while(|dir| file_count(self.fs, &dir))
+				.collect()
+		});
+
+		if let Some(idx) = (0..counts.len()).find(|idx| self.fs.exists(&part_dir(*idx).join(name)))
+		{
+			return part_dir(idx);
+		}
+
+		let idx = counts
+			.iter()
+			.position(|count| *count < max)
+			.unwrap_or(counts.len());
+
+		match counts.get_mut(idx) {
+			Some(count) => *count += 1,
+			None => counts.push(1),
+		}
+
+		part_dir(idx)
+	}
+}
+
+fn keep_source(target: &Target, dest_file: &Path, context: &Context) -> Result<Option<bool>> {
+	let ordering = match context.config.on_conflict {
+		Conflict::Newest => {
+			let existing = Target::try_from((dest_file, context.config, context.fs))?;
+			target.mtime.date.cmp(&existing.mtime.date)
+		}
+		Conflict::Largest => target.size.cmp(&context.fs.metadata(dest_file)?.len),
+		Conflict::Skip | Conflict::Rename => cmp::Ordering::Equal,
+	};
+
+	match ordering {
+		cmp::Ordering::Greater => Ok(Some(true)),
+		cmp::Ordering::Less => Ok(Some(false)),
+		cmp::Ordering::Equal => Ok(None),
+	}
+}
+
+fn is_sidecar(path: &Path) -> bool {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| SIDECAR_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+fn with_extension(base: &OsStr, extension: &str) -> OsString {
+	let mut name = base.to_os_string();
+	name.push(".");
+	name.push(extension);
+	name
+}
+
+fn file_count(fs: &dyn FileSystem, dir: &Path) -> Option<usize> {
+	let walk = Walk { min_depth: 1, max_depth: 1, ..Walk::default() };
+	let entries = fs
+		.walk(dir, walk, &mut |_| true)
+		.into_iter()
+		.collect::<std::result::Result<Vec<_>, _>>()
+		.ok()?;
+
+	Some(entries.iter().filter(|entry| !entry.is_dir()).count())
+}
+
+fn sidecars(fs: &dyn FileSystem, media: &Path, dest_file: &Path) -> Vec<(PathBuf, PathBuf)> {
+	let names = |path: &Path| {
+		let name = path.file_name()?.to_os_string();
+		let stem = path.file_stem()?.to_os_string();
+		Some([stem, name])
+	};
+
+	let (Some(dir), Some(dest_dir)) = (media.parent(), dest_file.parent()) else {
+		return Vec::new();
+	};
+
+	let (Some(bases), Some(dest_bases)) = (names(media), names(dest_file)) else {
+		return Vec::new();
+	};
+
+	let mut found = Vec::new();
+
+	for (base, dest_base) in bases.iter().zip(dest_bases) {
+		for extension in SIDECAR_EXTENSIONS {
+			let sidecar = [extension.to_string(), extension.to_uppercase()]
+				.into_iter()
+				.map(|extension| (dir.join(with_extension(base, &extension)), extension))
+				.find(|(sidecar, _)| fs.is_file(sidecar));
+
+			if let Some((sidecar, extension)) = sidecar {
+				let dest_sidecar = dest_dir.join(with_extension(&dest_base, &extension));
+				found.push((sidecar, dest_sidecar));
+			}
+		}
+	}
+
+	found
+}
+
+fn companion(fs: &dyn FileSystem, path: &Path, from: &[&str], to: &[&str]) -> Option<PathBuf> {
+	let extension = path.extension()?.to_str()?.to_lowercase();
+
+	if !from.contains(&extension.as_str()) {
+		return None;
+	}
+
+	let stem = path.file_stem()?;
+
+	to.iter()
+		.flat_map(|extension| [extension.to_string(), extension.to_uppercase()])
+		.map(|extension| path.with_file_name(with_extension(stem, &extension)))
+		.find(|companion| fs.is_file(companion))
+}
+
+fn apple_original(path: &Path) -> Option<String> {
+	let digits = path.file_stem()?.to_str()?.strip_prefix("IMG_E")?;
+
+	match digits.len() == 4 && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+		true => Some(format!("IMG_{digits}")),
+		false => None,
+	}
+}
+
+fn apple_edited(
+	entries: &[DirEntry],
+	config: &Config,
+	fs: &dyn FileSystem,
+) -> HashMap<PathBuf, PathBuf> {
+	let images: Vec<_> = entries
+		.iter()
+		.map(DirEntry::path)
+		.filter(|path| {
+			let path = path.to_path_buf();
+			matches!(
+				Extension::try_from((&path, config, fs)),
+				Ok(Extension::Image)
+			)
+		})
+		.collect();
+
+	let originals: HashMap<_, _> = images
+		.iter()
+		.filter_map(|path| Some(((path.parent()?, path.file_stem()?), *path)))
+		.collect();
+
+	images
+		.iter()
+		.filter_map(|path| {
+			let stem = apple_original(path)?;
+			let original = originals.get(&(path.parent()?, OsStr::new(&stem)))?;
+			Some((path.to_path_buf(), original.to_path_buf()))
+		})
+		.collect()
+}
+
+fn place_live_video(
+	entry: &DirEntry,
+	target: &Target,
+	person: &str,
+	dest_file: &Path,
+	context: &Context,
+	output: &mut Output,
+) {
+	let Some(video) = companion(
+		context.fs,
+		&target.abs_path,
+		LIVE_IMAGE_EXTENSIONS,
+		LIVE_VIDEO_EXTENSIONS,
+	) else {
+		return;
+	};
+
+	context.paired.lock().unwrap().insert(video.clone());
+
+	let (Some(stem), Some(extension)) = (dest_file.file_stem(), video.extension()) else {
+		return;
+	};
+
+	let mut name = stem.to_os_string();
+	name.push(".");
+	name.push(extension);
+
+	let dest_video = match context.config.live_video {
+		LiveVideo::Pictures => dest_file.with_file_name(name),
+		LiveVideo::Videos => {
+			let companion = Target { extension: Extension::Video, ..target.clone() };
+			dest_dir(entry, &companion, person, context.config).join(name)
+		}
+	};
+
+	if context.config.dry_run {
+		output.plan(&video, &dest_video);
+		return;
+	}
+
+	let placed = dest_video
+		.parent()
+		.map_or(Ok(()), |dir| {
+			create_dir(context.fs, dir, context.config.dir_mode)
+		})
+		.and_then(|_| {
+			let config = context.config;
+			place(
+				context.fs,
+				context.mode,
+				&video,
+				&dest_video,
+				config.retries,
+				config.verify.then_some(&context.progress),
+			)
+		});
+
+	match placed {
+		Ok(_) => {
+			let message = arrow(&video, &dest_video);
+			output.log(Level::Debug, message);
+
+			if let Err(err) = context.set_file_mode(&dest_video) {
+				output.log(
+					Level::Warn,
+					format!("Could not set mode of {}: {err}", dest_video.display()),
+				);
+			}
+
+			if let Err(err) = context.journal(&video, &dest_video) {
+				output.log(Level::Warn, format!("Could not write journal: {err}"));
+			}
+		}
+		Err(err) => output.log(Level::Warn, err.to_string()),
+	}
+}
+
+fn place_sidecars(target: &Target, dest_file: &Path, context: &Context, output: &mut Output) {
+	for (sidecar, dest_sidecar) in sidecars(context.fs, &target.abs_path, dest_file) {
+		if context.config.dry_run {
+			output.plan(&sidecar, &dest_sidecar);
+			continue;
+		}
+
+		match place(
+			context.fs,
+			context.mode,
+			&sidecar,
+			&dest_sidecar,
+			context.config.retries,
+			context.config.verify.then_some(&context.progress),
+		) {
+			Ok(_) => {
+				let message = arrow(&sidecar, &dest_sidecar);
+				output.log(Level::Debug, message);
+
+				if let Err(err) = context.set_file_mode(&dest_sidecar) {
+					let message =
+						format!("Could not set mode of {}: {err}", dest_sidecar.display());
+					output.log(Level::Warn, message);
+				}
+
+				if let Err(err) = context.journal(&sidecar, &dest_sidecar) {
+					output.log(Level::Warn, format!("Could not write journal: {err}"));
+				}
+			}
+			Err(err) => output.log(Level::Warn, err.to_string()),
+		}
+	}
+}
+
+fn archive_entry(
+	dest_dir: &Path,
+	year: &str,
+	name: &OsStr,
+	format: Archive,
+	config: &Config,
+) -> (PathBuf, String) {
+	let layout = dest_dir
+		.strip_prefix(&config.destination)
+		.unwrap_or(dest_dir);
+	let components: Vec<_> = layout
+		.components()
+		.map(|component| component.as_os_str().to_string_lossy().into_owned())
+		.collect();
+
+	let (outer, inner) = match components.iter().position(|component| component == year) {
+		Some(idx) => (&components[..idx], &components[idx + 1..]),
+		None => (&components[..0], &components[..]),
+	};
+
+	let archive = config
+		.destination
+		.join(outer.iter().collect::<PathBuf>())
+		.join(format!("{year}.{format}"));
+	let entry = inner
+		.iter()
+		.cloned()
+		.chain([name.to_string_lossy().into_owned()])
+		.collect::<Vec<_>>()
+		.join("/");
+
+	(archive, entry)
+}
+
+fn append_archive(
+	(writer, archived): &mut PendingArchive,
+	from: &Path,
+	archive: &Path,
+	entry: &str,
+	date: Option<i64>,
+	context: &Context,
+	output: &mut Output,
+) -> Result<()> {
+	let dest_file = archive.join(entry);
+
+	if writer.contains(entry) {
+		return Err(Error::DestinationExists(dest_file));
+	}
+
+	let modified = match date {
+		Some(date) => date,
+		None => context.fs.metadata(from)?.modified,
+	};
+
+	writer.append(entry, &mut context.fs.open(from)?, modified)?;
+	output.log(Level::Debug, arrow(from, &dest_file));
+	archived.push((from.to_path_buf(), dest_file));
+
+	Ok(())
+}
+
+fn place_archive(
+	format: Archive,
+	target: &Target,
+	dest_dir: &Path,
+	name: &OsStr,
+	context: &Context,
+	output: &mut Output,
+) -> Result<Action> {
+	let config = context.config;
+	let year = &target.mtime.year;
+	let (archive, entry) = archive_entry(dest_dir, year, name, format, config);
+	let dest_file = archive.join(&entry);
+	output.record.destination = Some(dest_file.clone());
+
+	let sidecars: Vec<_> = match config.no_sidecars {
+		true => Vec::new(),
+		false => sidecars(context.fs, &target.abs_path, &dest_dir.join(name))
+			.into_iter()
+			.filter_map(|(sidecar, dest_sidecar)| {
+				let name = dest_sidecar.file_name()?;
+				Some((
+					sidecar,
+					archive_entry(dest_dir, year, name, format, config).1,
+				))
+			})
+			.collect(),
+	};
+
+	if config.dry_run {
+		output.plan(&target.abs_path, &dest_file);
+
+		for (sidecar, entry) in &sidecars {
+			output.plan(sidecar, &archive.join(entry));
+		}
+
+		output.record.reason = Some("dry-run".to_string());
+		context.stats.lock().unwrap().add(target);
+		return Ok(Action::from(context.mode));
+	}
+
+	let mut archives = context.archives.lock().unwrap();
+	let writer = match archives.entry(archive.clone()) {
+		Entry::Occupied(writer) => writer.into_mut(),
+		Entry::Vacant(writer) => {
+			if let Some(dir) = archive.parent() {
+				create_dir(context.fs, dir, config.dir_mode)?;
+			}
+
+			writer.insert((
+				archive::Writer::open(context.fs, &archive, format)?,
+				Vec::new(),
+			))
+		}
+	};
+
+	let date = config
+		.dest_mtime
+		.then(|| target.mtime.timestamp(&config.timezone));
+
+	match append_archive(
+		writer,
+		&target.abs_path,
+		&archive,
+		&entry,
+		date,
+		context,
+		output,
+	) {
+		Ok(_) => {}
+		Err(Error::DestinationExists(_)) => {
+			output.log(
+				Level::Warn,
+				format!("File {} already exists!", dest_file.display()),
+			);
+			output.record.reason = Some("destination exists".to_string());
+			return Ok(Action::Skipped);
+		}
+		Err(Error::Io(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+			return Err(Error::Permission(target.abs_path.clone()));
+		}
+		Err(err) => return Err(err),
+	}
+
+	for (sidecar, entry) in &sidecars {
+		if let Err(err) = append_archive(writer, sidecar, &archive, entry, date, context, output) {
+			output.log(Level::Warn, err.to_string());
+		}
+	}
+
+	context.stats.lock().unwrap().add(target);
+	Ok(Action::from(context.mode))
+}
+
+fn relative_to_source<'a>(path: &'a Path, config: &Config) -> Option<&'a Path> {
+	config
+		.source
+		.iter()
+		.find_map(|source| path.strip_prefix(source).ok())
+}
+
+fn dest_dir(entry: &DirEntry, target: &Target, person: &str, config: &Config) -> PathBuf {
+	let layout = match (config.flatten, config.preserve_tree) {
+		(true, _) => Path::new(&target.mtime.year).join(&target.mtime.month),
+		(_, true) => relative_to_source(entry.path(), config)
+			.and_then(Path::parent)
+			.map(Path::to_path_buf)
+			.unwrap_or_default(),
+		_ => config
+			.template
+			.render(target, &config.kind_dir(&target.extension), person),
+	};
+
+	let layout = match (config.group_by_camera, &target.camera) {
+		(true, Some(camera)) => layout.join(camera),
+		(true, None) => layout.join("unknown-camera"),
+		(false, _) => layout,
+	};
+
+	let layout = match target.aspect {
+		Some(aspect) => layout.join(aspect),
+		None => layout,
+	};
+
+	let layout = match (config.sort_videos_by, target.duration) {
+		(Some(VideoSort::Duration), Some(duration)) => {
+			match duration < Duration::from_secs(config.duration_threshold) {
+				true => layout.join("short"),
+				false => layout.join("long"),
+			}
+		}
+		_ => layout,
+	};
+
+	let layout = match config.geo {
+		true => layout.join(location(target, config)),
+		false => layout,
+	};
+
+	let layout = match &target.burst {
+		Some(burst) => layout.join(burst),
+		None => layout,
+	};
+
+	let layout = match target.apple_original {
+		true => layout.join("originals"),
+		false => layout,
+	};
+
+	extended_length(config.destination.join(layout))
+}
+
+#[cfg(windows)]
+fn extended_length(path: PathBuf) -> PathBuf {
+	if path.as_os_str().len() < MAX_PATH {
+		return path;
+	}
+
+	let (prefix, rest) = match path.to_str().and_then(|path| path.strip_prefix(r"\\")) {
+		Some(rest) if rest.starts_with(r"?\") => return path,
+		Some(rest) => (r"\\?\UNC\", OsStr::new(rest)),
+		None => (r"\\?\", path.as_os_str()),
+	};
+
+	let mut extended = OsString::from(prefix);
+	extended.push(rest);
+	PathBuf::from(extended)
+}
+
+#[cfg(not(windows))]
+fn extended_length(path: PathBuf) -> PathBuf {
+	path
+}
+
+fn location(target: &Target, config: &Config) -> String {
+	let Some((latitude, longitude)) = target.gps else {
+		return "unknown-location".to_string();
+	};
+
+	let precision = (0..6)
+		.find(|digits| {
+			let scaled = config.geo_grid * 10f64.powi(*digits);
+			(scaled - scaled.round()).abs() < 1e-9
+		})
+		.unwrap_or(6) as usize;
+	let bucket = |value: f64| (value / config.geo_grid).floor() * config.geo_grid;
+	let (latitude, longitude) = (bucket(latitude), bucket(longitude));
+	let north = match latitude < 0.0 {
+		true => 'S',
+		false => 'N',
+	};
+	let east = match longitude < 0.0 {
+		true => 'W',
+		false => 'E',
+	};
+
+	format!(
+		"{north}{:.precision$}_{east}{:.precision$}",
+		latitude.abs(),
+		longitude.abs()
+	)
+}
+
+fn top_level_dir(path: &Path, config: &Config) -> Option<String> {
+	let mut components = relative_to_source(path, config)?.components();
+	let dir = components.next()?;
+
+	components.next()?;
+	Some(dir.as_os_str().to_string_lossy().into_owned())
+}
+
+fn person(entry: &DirEntry, target: &Target, config: &Config) -> String {
+	if config.owner_from_exif_artist {
+		return target
+			.artist
+			.clone()
+			.or(config.person.clone())
+			.unwrap_or("unknown".to_string());
+	}
+
+	match config.person_from {
+		Some(PersonFrom::Dir) => top_level_dir(entry.path(), config).or(config.person.clone()),
+		None => config.person.clone(),
+	}
+	.unwrap_or_default()
+}
+
+fn dedup_candidates(entries: &[DirEntry], context: &Context) -> HashSet<PathBuf> {
+	let files: Vec<_> = entries
+		.par_iter()
+		.filter_map(|entry| {
+			let metadata = context.fs.entry_metadata(entry).ok()?;
+			(!metadata.is_dir).then(|| (metadata.len, entry.path().to_path_buf()))
+		})
+		.collect();
+
+	let mut sizes: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+	for (len, path) in files {
+		sizes.entry(len).or_default().push(path);
+	}
+
+	let prefixes: Vec<_> = sizes
+		.into_iter()
+		.filter(|(_, paths)| paths.len() > 1)
+		.flat_map(|(len, paths)| paths.into_iter().map(move |path| (len, path)))
+		.collect::<Vec<_>>()
+		.into_par_iter()
+		.map(|(len, path)| {
+			let prefix = context.fs.open(&path).and_then(hash::hash_prefix).ok();
+			((len, prefix), path)
+		})
+		.collect();
+
+	let mut candidates: HashMap<(u64, Option<Hash>), Vec<PathBuf>> = HashMap::new();
+
+	for (key, path) in prefixes {
+		candidates.entry(key).or_default().push(path);
+	}
+
+	candidates
+		.into_iter()
+		.filter(|((_, prefix), paths)| prefix.is_none() || paths.len() > 1)
+		.flat_map(|(_, paths)| paths)
+		.collect()
+}
+
+fn bursts(entries: &[DirEntry], context: &Context) -> HashMap<PathBuf, String> {
+	let config = context.config;
+	let mut frames: Vec<_> = entries
+		.par_iter()
+		.filter_map(|entry| {
+			let target = Target::try_from((entry, config, context.fs)).ok()?;
+
+			if !matches!(target.extension, Extension::Image) {
+				return None;
+			}
+
+			let dir = dest_dir(entry, &target, &person(entry, &target, config), config);
+			Some((dir, target.mtime.date, target.abs_path))
+		})
+		.collect();
+
+	frames.sort();
+
+	let window = chrono::Duration::seconds(config.burst_window.into());
+	let mut bursts = HashMap::new();
+
+	for frames in frames.chunk_by(|a, b| a.0 == b.0 && b.1 - a.1 <= window) {
+		if (frames.len() as u64) < config.burst_min {
+			continue;
+		}
+
+		let burst = frames[0].1.format("burst-%Y%m%d-%H%M%S").to_string();
+		bursts.extend(
+			frames
+				.iter()
+				.map(|(_, _, path)| (path.clone(), burst.clone())),
+		);
+	}
+
+	bursts
+}
+
+fn in_range(target: &Target, config: &Config) -> Result<()> {
+	if config.min_size.is_some_and(|min| target.size < min)
+		|| config.max_size.is_some_and(|max| target.size > max)
+	{
+		return Err(Error::Size(target.abs_path.clone()));
+	}
+
+	if config.skip_empty && target.size == 0 {
+		return Err(Error::Empty(target.abs_path.clone()));
+	}
+
+	let date = target.mtime.date.date();
+
+	if config.since.is_some_and(|since| date < since)
+		|| config.until.is_some_and(|until| date > until)
+		|| config
+			.last_run
+			.is_some_and(|last_run| target.modified < last_run)
+	{
+		return Err(Error::DateRange(target.abs_path.clone()));
+	}
+
+	Ok(())
+}
+
+fn process(entry: &DirEntry, context: &Context, output: &mut Output) -> Result<Action> {
+	let config = context.config;
+
+	if entry.path_is_symlink()
+		&& !context
+			.fs
+			.metadata(entry.path())
+			.is_ok_and(|metadata| metadata.is_dir)
+		&& matches!(config.symlink, Symlink::Skip)
+	{
+		return Err(Error::Symlink(entry.path().to_path_buf()));
+	}
+
+	if !config.no_sidecars && is_sidecar(entry.path()) {
+		return Err(Error::Sidecar(entry.path().to_path_buf()));
+	}
+
+	if context.paired.lock().unwrap().contains(entry.path()) {
+		return Err(Error::LivePhoto(entry.path().to_path_buf()));
+	}
+
+	if let Some(destination) = context.processed(entry.path()) {
+		output.log(
+			Level::Info,
+			format!(
+				"Already processed {} as {}",
+				entry.path().display(),
+				destination.display()
+			),
+		);
+		output.record.destination = Some(destination.clone());
+		output.record.reason = Some("already processed".to_string());
+		return Ok(Action::Present);
+	}
+
+	let mut target = Target::try_from((entry, config, context.fs))?;
+	target.burst = context.bursts.get(&target.abs_path).cloned();
+	target.apple_original =
+		config.apple_originals && context.apple_originals.contains(entry.path());
+
+	if let Some(mtime) = context.apple_edited.get(entry.path()) {
+		target.mtime = mtime.clone();
+	}
+
+	output.log(
+		Level::Debug,
+		format!(
+			"Date of {} from {}",
+			target.abs_path.display(),
+			target.mtime.source
+		),
+	);
+
+	in_range(&target, config)?;
+
+	if config.validate_media && !sniff::valid(context.fs, &target.abs_path) {
+		return Err(Error::Corrupt(target.abs_path));
+	}
+
+	if config.dedup {
+		if let Some(original) = context.duplicate_of(entry, &target)? {
+			output.log(
+				Level::Warn,
+				format!(
+					"Duplicate {} of {}",
+					target.abs_path.display(),
+					original.display()
+				),
+			);
+			output.record.reason = Some(format!("duplicate of {}", original.display()));
+			return Ok(Action::Skipped);
+		}
+	}
+
+	if config.stats_only {
+		context.stats.lock().unwrap().add(&target);
+		return Ok(Action::Skipped);
+	}
+
+	let person = person(entry, &target, config);
+	let dest_dir = dest_dir(entry, &target, &person, config);
+
+	let dry_run = config.dry_run;
+	let prefix = match dry_run {
+		true => "[dry-run] ",
+		false => "",
+	};
+
+	let kind = config.kind_dir(&target.extension);
+	let name = match (&config.rename_pattern, &config.rename_on_move) {
+		(Some(pattern), _) => pattern.rename(&target, &kind, &person),
+		(None, Some(template)) => template
+			.file_name(&target, &kind, &person, context.sequence(&dest_dir))
+			.ok_or(Error::EmptyName(target.abs_path.clone()))?,
+		(None, None) => target.name.clone(),
+	};
+
+	let name = match config.dest_structure {
+		Some(DestStructure::YearPrefixed) => {
+			let mut prefixed = OsString::from(format!("{}-", target.mtime.month_number));
+			prefixed.push(name);
+			prefixed
+		}
+		_ => name,
+	};
+
+	let name = match (config.normalize_unicode, name.to_str()) {
+		(true, Some(text)) => OsString::from(unicode::nfc(text)),
+		_ => name,
+	};
+
+	let name = match config.lowercase_extensions {
+		true => lowercase_extension(&name),
+		false => name,
+	};
+
+	let dest_dir = match config.max_per_dir {
+		Some(max) => context.part(&dest_dir, &name, max as usize),
+		None => dest_dir,
+	};
+
+	if let Some(format) = config.archive {
+		return place_archive(format, &target, &dest_dir, &name, context, output);
+	}
+
+	if context.fs.exists(&dest_dir) {
+		output.log(
+			Level::Debug,
+			format!("{prefix}Directory {} already created!", dest_dir.display()),
+		);
+	} else if !dry_run {
+		create_dir(context.fs, &dest_dir, config.dir_mode)?;
+	}
+
+	let existing = dest_dir.join(&name);
+
+	if let Some(against) = &config.against {
+		let compared = match existing.strip_prefix(&config.destination) {
+			Ok(relative) => against.join(relative),
+			Err(_) => existing.clone(),
+		};
+		let (change, action) = match context.fs.exists(&compared) {
+			false => (Change::New, Action::from(context.mode)),
+			true => match context.is_imported(&target.abs_path, &compared)? {
+				true => (Change::Identical, Action::Present),
+				false => (Change::Collision, Action::Skipped),
+			},
+		};
+
+		output.record.destination = Some(compared.clone());
+		output.record.reason = Some(change.to_string());
+		context
+			.diff
+			.lock()
+			.unwrap()
+			.add(change, target.abs_path, compared);
+		return Ok(action);
+	}
+
+	if config.newer_than_dest
+		&& context.fs.exists(&existing)
+		&& context.is_imported(&target.abs_path, &existing)?
+	{
+		output.log(
+			Level::Info,
+			format!(
+				"{prefix}Already imported {} as {}",
+				target.abs_path.display(),
+				existing.display()
+			),
+		);
+		output.record.destination = Some(existing);
+		output.record.reason = Some("already imported".to_string());
+		return Ok(Action::Present);
+	}
+
+	let Some(dest_file) = context.claim(&dest_dir, &name) else {
+		output.log(
+			Level::Warn,
+			format!("{prefix}File {} already exists!", existing.display()),
+		);
+		output.record.destination = Some(existing);
+		output.record.reason = Some("destination exists".to_string());
+		return Ok(Action::Skipped);
+	};
+
+	output.record.destination = Some(dest_file.clone());
+
+	let mut displaced = None;
+
+	if context.fs.exists(&dest_file) {
+		let keep_source = keep_source(&target, &dest_file, context)?;
+		let duplicate = match keep_source {
+			Some(false) => context.is_imported(&target.abs_path, &dest_file)?,
+			_ => false,
+		};
+		let message = match (keep_source, duplicate && matches!(context.mode, Mode::Move)) {
+			(None, _) => format!(
+				"{} and {} are tied, left both in place",
+				dest_file.display(),
+				target.abs_path.display()
+			),
+			(Some(true), _) | (Some(false), true) => {
+				let (kept, discarded) = match keep_source {
+					Some(true) => (&target.abs_path, &dest_file),
+					_ => (&dest_file, &target.abs_path),
+				};
+				format!("kept {}, discarded {}", kept.display(), discarded.display())
+			}
+			(Some(false), false) => format!(
+				"kept {}, left {} in place",
+				dest_file.display(),
+				target.abs_path.display()
+			),
+		};
+		output.log(Level::Info, format!("{prefix}Collision: {message}"));
+		output.record.reason = Some(message);
+
+		match (keep_source, dry_run) {
+			(None | Some(false), _) => {
+				if duplicate && !dry_run && matches!(context.mode, Mode::Move) {
+					context.fs.remove_file(&target.abs_path)?;
+				}
+
+				return Ok(Action::Skipped);
+			}
+			(Some(true), true) => {}
+			(Some(true), false) => {
+				let temp = temp_path(&dest_file);
+				context.fs.rename(&dest_file, &temp)?;
+				displaced = Some(temp);
+			}
+		}
+	}
+
+	if dry_run {
+		output.plan(&target.abs_path, &dest_file);
+		output.record.reason = Some("dry-run".to_string());
+
+		if !config.no_sidecars {
+			place_sidecars(&target, &dest_file, context, output);
+		}
+
+		if config.pair_live_photos {
+			place_live_video(entry, &target, &person, &dest_file, context, output);
+		}
+
+		context.stats.lock().unwrap().add(&target);
+		return Ok(Action::from(context.mode));
+	}
+
+	let placed = match target.link {
+		true => place_link(context.fs, context.mode, &target.abs_path, &dest_file),
+		false => place(
+			context.fs,
+			context.mode,
+			&target.abs_path,
+			&dest_file,
+			config.retries,
+			config.verify.then_some(&context.progress),
+		),
+	};
+
+	if let Some(displaced) = displaced {
+		match placed {
+			Ok(_) => context.fs.remove_file(&displaced)?,
+			Err(_) => context.fs.rename(&displaced, &dest_file)?,
+		}
+	}
+
+	let placed = match (placed, config.dest_mtime && !target.link) {
+		(Ok(_), true) => Ok(context
+			.fs
+			.set_mtime(&dest_file, target.mtime.timestamp(&config.timezone))?),
+		(placed, _) => placed,
+	};
+
+	match placed {
+		Ok(_) => {
+			output.log(Level::Debug, arrow(&target.abs_path, &dest_file));
+
+			let moded = match target.link {
+				true => Ok(()),
+				false => context.set_file_mode(&dest_file),
+			};
+
+			if let Err(err) = moded {
+				output.log(
+					Level::Warn,
+					format!("Could not set mode of {}: {err}", dest_file.display()),
+				);
+			}
+
+			if let Err(err) = context.journal(&target.abs_path, &dest_file) {
+				output.log(Level::Warn, format!("Could not write journal: {err}"));
+			}
+
+			if config.link_back {
+				if let Err(err) = context.fs.symlink(&dest_file, &target.abs_path) {
+					output.log(
+						Level::Warn,
+						format!(
+							"Could not link {} back to {}: {err}",
+							target.abs_path.display(),
+							dest_file.display()
+						),
+					);
+				}
+			}
+
+			if !config.no_sidecars {
+				place_sidecars(&target, &dest_file, context, output);
+			}
+
+			if config.pair_live_photos {
+				place_live_video(entry, &target, &person, &dest_file, context, output);
+			}
+
+			context.stats.lock().unwrap().add(&target);
+
+			if let Some(exec) = &config.exec {
+				if let Err(err) = exec.run(&dest_file) {
+					let err = Error::Exec(dest_file, err);
+
+					match config.strict {
+						true => return Err(err),
+						false => output.log(Level::Warn, err.to_string()),
+					}
+				}
+			}
+
+			Ok(Action::from(context.mode))
+		}
+		Err(Error::DestinationExists(_)) => {
+			output.log(
+				Level::Warn,
+				format!("File {} already exists!", dest_file.display()),
+			);
+			output.record.reason = Some("destination exists".to_string());
+			Ok(Action::Skipped)
+		}
+		Err(Error::Io(err)) if err.kind() == io::ErrorKind::AlreadyExists => {
+			output.log(
+				Level::Warn,
+				format!("File {} already exists!", dest_file.display()),
+			);
+			output.record.reason = Some("destination exists".to_string());
+			Ok(Action::Skipped)
+		}
+		Err(Error::Io(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+			Err(Error::Permission(target.abs_path))
+		}
+		Err(err) => Err(err),
+	}
+}
+
+fn remove_empty_dirs(
+	config: &Config,
+	entries: &[DirEntry],
+	fs: &dyn FileSystem,
+	output: &mut Output,
+) {
+	let populated: HashSet<&Path> = entries
+		.iter()
+		.filter(|entry| entry.depth() > 0)
+		.filter_map(|entry| entry.path().parent())
+		.collect();
+
+	let walk = Walk { min_depth: 1, ..Walk::default() };
+	let dirs = config
+		.source
+		.iter()
+		.flat_map(|source| fs.walk(source, walk, &mut |_| true).into_iter().rev())
+		.filter_map(|entry| entry.ok())
+		.filter(|entry| entry.is_dir() && populated.contains(entry.path()));
+
+	for dir in dirs {
+		if fs.remove_dir(dir.path()).is_ok() {
+			output.log(
+				Level::Debug,
+				format!("Removed empty directory {}", dir.path().display()),
+			);
+		}
+	}
+}
+
+fn report(err: &Error, config: &Config, output: &mut Output) -> Option<Action> {
+	let action = match (err, config.skip_unreadable) {
+		(Error::Permission(_), true) => Some(Action::Skipped),
+		_ => err.action(),
+	};
+	let level = match action {
+		Some(Action::Failed) => Level::Error,
+		Some(_) => Level::Warn,
+		None => Level::Debug,
+	};
+
+	match (config.format, level <= output.level) {
+		(Format::Json, true) => output.stderr.push(err.to_json()),
+		(Format::Json, false) => {}
+		(Format::Human, _) => output.log(level, err.to_string()),
+	}
+
+	output.record.reason = Some(err.to_string());
+	action
+}
+
+fn quarantine(path: &Path, dir: &Path, context: &Context, output: &mut Output) {
+	let config = context.config;
+	let relative = relative_to_source(path, config)
+		.filter(|relative| !relative.as_os_str().is_empty())
+		.or_else(|| path.file_name().map(Path::new))
+		.unwrap_or(path);
+	let dest_file = dir.join(relative);
+
+	if config.dry_run {
+		output.plan(path, &dest_file);
+		return;
+	}
+
+	let placed = dest_file
+		.parent()
+		.map_or(Ok(()), |dir| {
+			create_dir(context.fs, dir, context.config.dir_mode)
+		})
+		.and_then(|_| {
+			place(
+				context.fs,
+				context.mode,
+				path,
+				&dest_file,
+				config.retries,
+				config.verify.then_some(&context.progress),
+			)
+		});
+
+	match placed {
+		Ok(_) => {
+			let message = format!("Quarantined {} as {}", path.display(), dest_file.display());
+			output.log(Level::Info, message);
+			output.record.destination = Some(dest_file.clone());
+
+			if let Err(err) = context.journal(path, &dest_file) {
+				output.log(Level::Warn, format!("Could not write journal: {err}"));
+			}
+		}
+		Err(err) => output.log(
+			Level::Warn,
+			format!("Could not quarantine {}: {err}", path.display()),
+		),
+	}
+}
+
+fn readable(entries: fs::Walked) -> Result<(Vec<DirEntry>, Vec<PathBuf>)> {
+	let mut readable = Vec::new();
+	let mut unreadable = Vec::new();
+
+	for entry in entries {
+		match entry {
+			Ok(entry) => readable.push(entry),
+			Err(err) if err.err.kind() == io::ErrorKind::PermissionDenied => {
+				unreadable.push(err.path.unwrap_or_default())
+			}
+			Err(err) => return Err(err.into()),
+		}
+	}
+
+	Ok((readable, unreadable))
+}
+
+fn limit(
+	mut entries: Vec<DirEntry>,
+	max: usize,
+	config: &Config,
+	fs: &dyn FileSystem,
+) -> (Vec<DirEntry>, usize) {
+	let media: Vec<_> = entries
+		.iter()
+		.enumerate()
+		.filter(|(_, entry)| {
+			!entry.is_dir()
+				&& Extension::try_from((&entry.path().to_path_buf(), config, fs)).is_ok()
+		})
+		.map(|(idx, _)| idx)
+		.collect();
+
+	match media.get(max) {
+		Some(&idx) => {
+			entries.truncate(idx);
+			(entries, media.len() - max)
+		}
+		None => (entries, 0),
+	}
+}
+
+fn listed_paths(config: &Config, fs: &dyn FileSystem) -> Result<Option<Vec<PathBuf>>> {
+	if !config.paths.is_empty() {
+		return Ok(Some(config.paths.clone()));
+	}
+
+	let text = match (config.from_stdin, &config.files_from) {
+		(true, _) => io::read_to_string(io::stdin())?,
+		(_, Some(list)) => fs.read_to_string(list)?,
+		_ => return Ok(None),
+	};
+
+	let paths = text
+		.lines()
+		.map(str::trim_end)
+		.filter(|line| !line.is_empty())
+		.map(PathBuf::from)
+		.collect();
+
+	Ok(Some(paths))
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+	entry.file_name().to_string_lossy().starts_with('.')
+}
+
+fn excluded(entry: &DirEntry, config: &Config, ignore: &mut Ignore) -> bool {
+	let is_dir = entry.is_dir();
+
+	entry.depth() > 0
+		&& ((!config.include_hidden && is_hidden(entry))
+			|| (!config.no_ignore && ignore.ignored(entry))
+			|| config
+				.exclude
+				.iter()
+				.any(|glob| glob.matches(entry.path(), is_dir)))
+}
+
+struct JournalEntry {
+	from: PathBuf,
+	to: PathBuf,
+	action: String,
+	time: Option<u64>,
+	size: Option<u64>,
+	modified: Option<i64>,
+}
+
+impl JournalEntry {
+	fn parse(entry: &Value) -> Option<Self> {
+		let from = entry.get("from")?.as_path()?;
+		let to = entry.get("to")?.as_path()?;
+		let action = entry
+			.get("action")
+			.and_then(Value::as_str)
+			.unwrap_or("moved");
+		let time = entry.get("time").and_then(Value::as_u64);
+		let size = entry.get("size").and_then(Value::as_u64);
+		let modified = entry.get("modified").and_then(Value::as_i64);
+
+		Some(Self { from, to, action: action.to_string(), time, size, modified })
+	}
+}
+
+fn processed(journal: &Path, fs: &dyn FileSystem) -> Result<HashMap<(PathBuf, u64, i64), PathBuf>> {
+	let entries = match fs.read_to_string(journal) {
+		Ok(entries) => entries,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+		Err(err) => return Err(err.into()),
+	};
+
+	let processed = entries
+		.lines()
+		.filter_map(|line| Value::parse(line).as_ref().and_then(JournalEntry::parse))
+		.filter(|entry| fs.exists(&entry.to))
+		.filter_map(|entry| Some(((entry.from, entry.size?, entry.modified?), entry.to)))
+		.collect();
+
+	Ok(processed)
+}
+
+fn last_run(destination: &Path, fs: &dyn FileSystem) -> Result<Option<i64>> {
+	let marker = match fs.read_to_string(&destination.join(LAST_RUN)) {
+		Ok(marker) => marker,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(err) => return Err(Error::Io(err)),
+	};
+
+	match marker.trim().parse() {
+		Ok(time) => Ok(Some(time)),
+		Err(_) => Err(Error::Io(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Invalid marker {}", destination.join(LAST_RUN).display()),
+		))),
+	}
+}
+
+fn save_last_run(destination: &Path, time: i64, fs: &dyn FileSystem) -> Result<()> {
+	let marker = destination.join(LAST_RUN);
+	let tmp = temp_path(&marker);
+	fs.write(&tmp, format!("{time}\n").as_bytes())?;
+	fs.rename(&tmp, &marker)?;
+	Ok(())
+}
+
+pub fn prune(
+	journal: &Path,
+	older_than: Duration,
+	dry_run: bool,
+	fs: &dyn FileSystem,
+) -> Result<()> {
+	let entries = fs.read_to_string(journal)?;
+	let now = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+
+	for line in entries.lines().filter(|line| !line.trim().is_empty()) {
+		let Some(entry) = Value::parse(line).as_ref().and_then(JournalEntry::parse) else {
+			eprintln!(
+				"{}",
+				format!("Warning: Invalid journal entry: {line}").yellow()
+			);
+			continue;
+		};
+
+		if !matches!(entry.action.as_str(), "copied" | "linked") || !fs.exists(&entry.from) {
+			continue;
+		}
+
+		let Some(time) = entry.time else {
+			eprintln!(
+				"Warning: {} has no copy time, not pruning",
+				entry.from.display()
+			);
+			continue;
+		};
+
+		if now.saturating_sub(time) < older_than.as_secs() {
+			continue;
+		}
+
+		let identical =
+			|| -> io::Result<bool> {
+				Ok(hash::hash_reader(fs.open(&entry.from)?)?
+					== hash::hash_reader(fs.open(&entry.to)?)?)
+			};
+		let copied = match (
+			entry.size,
+			entry.modified,
+			fs.metadata(&entry.from),
+			fs.metadata(&entry.to),
+		) {
+			(Some(size), Some(modified), Ok(from), Ok(to)) => {
+				from.len == size
+					&& from.modified == modified
+					&& to.len == size
+					&& identical().unwrap_or(false)
+			}
+			_ => false,
+		};
+
+		if !copied {
+			eprintln!(
+				"Warning: {} is missing or differs, not pruning {}",
+				entry.to.display(),
+				entry.from.display()
+			);
+			continue;
+		}
+
+		match dry_run {
+			true => println!("[dry-run] removed {}", entry.from.display()),
+			false => match fs.remove_file(&entry.from) {
+				Ok(_) => println!("removed {}", entry.from.display()),
+				Err(err) => eprintln!("{}", format!("Error: {err}").red()),
+			},
+		}
+	}
+
+	Ok(())
+}
+
+pub fn undo(journal: &Path, fs: &dyn FileSystem) -> Result<()> {
+	let entries = fs.read_to_string(journal)?;
+
+	for line in entries.lines().rev().filter(|line| !line.trim().is_empty()) {
+		let entry = Value::parse(line);
+		let Some(entry) = entry.as_ref().and_then(JournalEntry::parse) else {
+			eprintln!(
+				"{}",
+				format!("Warning: Invalid journal entry: {line}").yellow()
+			);
+			continue;
+		};
+
+		if entry.action != "moved" {
+			continue;
+		}
+
+		let JournalEntry { from, to, .. } = entry;
+
+		if !fs.exists(&to) {
+			eprintln!(
+				"{}",
+				format!(
+					"Warning: {} no longer exists, cannot restore {}",
+					to.display(),
+					from.display()
+				)
+				.yellow()
+			);
+			continue;
+		}
+
+		if fs.read_link(&from).is_ok_and(|link| link == to) {
+			fs.remove_file(&from)?;
+		}
+
+		if fs.exists(&from) {
+			eprintln!(
+				"{}",
+				format!("Warning: File {} already exists!", from.display()).yellow()
+			);
+			continue;
+		}
+
+		let restored = match from.parent() {
+			Some(parent) => create_dir(fs, parent, None).and_then(|_| rename(fs, &to, &from, 0)),
+			None => rename(fs, &to, &from, 0),
+		};
+
+		match restored {
+			Ok(_) => println!("{}", arrow(&to, &from)),
+			Err(err) => eprintln!("{}", format!("Error: {err}").red()),
+		}
+	}
+
+	Ok(())
+}
+
+fn parse_year(year: &str) -> Option<i32> {
+	match year.len() {
+		4 => year.parse().ok(),
+		_ => None,
+	}
+}
+
+fn folder_date(relative: &Path) -> Option<(i32, Option<u32>)> {
+	let components: Vec<_> = relative
+		.parent()?
+		.components()
+		.filter_map(|component| component.as_os_str().to_str())
+		.collect();
+
+	components.iter().enumerate().find_map(|(idx, component)| {
+		let next = components.get(idx + 1).copied().unwrap_or_default();
+
+		match (parse_year(component), component.split_once('-')) {
+			(Some(year), _) => Some((year, locale::parse_month(next))),
+			(None, Some((year, month))) => Some((parse_year(year)?, locale::parse_month(month))),
+			(None, None) => Some((parse_year(next)?, Some(locale::parse_month(component)?))),
+		}
+	})
+}
+
+pub fn verify_archive(root: &Path, timezone: &Timezone, fs: &dyn FileSystem) -> Result<usize> {
+	let mut misfiled = 0;
+
+	let walk = Walk { min_depth: 1, ..Walk::default() };
+
+	for entry in fs.walk(root, walk, &mut |_| true) {
+		let entry = entry?;
+		let path = entry.path();
+
+		if entry.is_dir() || is_sidecar(path) {
+			continue;
+		}
+
+		let Some((year, month)) = path.strip_prefix(root).ok().and_then(folder_date) else {
+			continue;
+		};
+
+		let date = match Exif::read(fs, path).and_then(|exif| exif.date_time_original()) {
+			Some(date) => date,
+			None => {
+				let secs = fs.metadata(path)?.modified;
+				let date =
+					DateTime::from_timestamp(secs, 0).ok_or(Error::DateTime(path.to_path_buf()))?;
+				timezone.localize(date)
+			}
+		};
+
+		if date.year() == year && month.is_none_or(|month| date.month() == month) {
+			continue;
+		}
+
+		let folder = match month {
+			Some(month) => format!("{year}/{month:02}"),
+			None => year.to_string(),
+		};
+
+		println!(
+			"{}: filed under {folder}, dated {}",
+			path.display(),
+			date.format("%Y-%m-%d")
+		);
+		misfiled += 1;
+	}
+
+	println!("misfiled: {misfiled}");
+	Ok(misfiled)
+}
+
+fn self_test(fs: &dyn FileSystem) -> io::Result<()> {
+	let dir = std::env::temp_dir().join(format!(".tf-doctor-{}", std::process::id()));
+	let file = dir.join("file");
+	let copied = dir.join("copied");
+	let renamed = dir.join("renamed");
+	let linked = dir.join("linked");
+
+	let tested = fs
+		.create_dir_all(&dir)
+		.and_then(|_| fs.write(&file, b"tf"))
+		.and_then(|_| fs.copy(&file, &copied))
+		.and_then(|_| fs.rename(&copied, &renamed))
+		.and_then(|_| fs.hard_link(&file, &linked))
+		.and_then(|_| fs.set_mtime(&renamed, 0))
+		.and_then(|_| match fs.metadata(&renamed)?.modified {
+			0 => Ok(()),
+			_ => Err(io::Error::other("modification time was not preserved")),
+		})
+		.and_then(|_| fs.remove_file(&linked));
+
+	for path in [&file, &copied, &renamed, &linked] {
+		let _ = fs.remove_file(path);
+	}
+
+	let _ = fs.remove_dir(&dir);
+	tested
+}
+
+pub fn doctor(require: &[Capability]) -> Result<()> {
+	let ffprobe = duration::ffprobe_available();
+	let filesystem = self_test(&RealFileSystem);
+	let timezone = std::env::var("TZ").unwrap_or("system".to_string());
+
+	println!("features: exif, mp4 duration, zip, tar, geo grid");
+	println!("exif: built in");
+	println!(
+		"ffprobe: {}",
+		match ffprobe {
+			true => "found",
+			false => "not found on PATH, falling back to the built-in mp4 reader",
+		}
+	);
+	println!("timezone: {timezone} ({})", Local::now().format("%:z"));
+
+	match &filesystem {
+		Ok(_) => println!("filesystem: ok"),
+		Err(err) => println!("filesystem: {}", format!("failed: {err}").red()),
+	}
+
+	let unavailable = require.iter().find(|capability| match capability {
+		Capability::Exif => false,
+		Capability::Ffprobe => !ffprobe,
+		Capability::Filesystem => filesystem.is_err(),
+	});
+
+	match unavailable {
+		Some(capability) => Err(Error::Unavailable(*capability)),
+		None => Ok(()),
+	}
+}
+
+fn preflight(entries: &[DirEntry], mode: Mode, config: &Config, fs: &dyn FileSystem) -> Result<()> {
+	let Some(available) = fs.available_space(&config.destination) else {
+		return Ok(());
+	};
+
+	let needed = entries
+		.par_iter()
+		.filter(|entry| {
+			matches!(mode, Mode::Copy)
+				|| config.archive.is_some()
+				|| !fs.same_device(entry.path(), &config.destination)
+		})
+		.filter_map(|entry| Target::try_from((entry, config, fs)).ok())
+		.filter(|target| in_range(target, config).is_ok())
+		.map(|target| target.size)
+		.sum();
+
+	match needed > available {
+		true => Err(Error::NoSpace(
+			config.destination.clone(),
+			needed,
+			available,
+		)),
+		false => Ok(()),
+	}
+}
+
+pub fn init(dir: &Path, fs: &dyn FileSystem) -> Result<()> {
+	let marker = dir.join(MARKER);
+
+	if fs.exists(&marker) {
+		println!("{} is already initialized", dir.display());
+		return Ok(());
+	}
+
+	fs.create_dir_all(dir)?;
+	fs.write(&marker, b"")?;
+	println!("Initialized {}", dir.display());
+	Ok(())
+}
+
+pub fn organize(config: Config) -> Result<Summary> {
+	organize_with(config, &RealFileSystem)
+}
+
+fn walk_source(source: &Path, config: &Config, fs: &dyn FileSystem) -> fs::Walked {
+	let walk = Walk {
+		follow_links: config.follow_symlinks,
+		min_depth: config.min_depth.unwrap_or(0),
+		max_depth: config.max_depth.unwrap_or(usize::MAX),
+	};
+	let mut ignore = Ignore::new(source, fs);
+
+	fs.walk(source, walk, &mut |entry| {
+		!excluded(entry, config, &mut ignore)
+	})
+}
+
+fn snapshot(config: &Config, fs: &dyn FileSystem) -> HashMap<PathBuf, (u64, i64)> {
+	config
+		.source
+		.iter()
+		.flat_map(|source| walk_source(source, config, fs))
+		.filter_map(|entry| entry.ok())
+		.filter(|entry| !entry.is_dir())
+		.filter_map(|entry| {
+			let metadata = fs.entry_metadata(&entry).ok()?;
+			Some((entry.into_path(), (metadata.len, metadata.modified)))
+		})
+		.collect()
+}
+
+pub fn watch(config: Config, fs: &dyn FileSystem) -> Result<()> {
+	let interval = Duration::from_secs(config.watch_interval);
+	let mut previous = HashMap::new();
+	let mut handled = HashSet::new();
+
+	loop {
+		let current = snapshot(&config, fs);
+		handled.retain(|(path, stat)| current.get(path) == Some(stat));
+
+		let ready: Vec<_> = current
+			.iter()
+			.filter(|(path, stat)| {
+				previous.get(*path) == Some(*stat) && !handled.contains(&((*path).clone(), **stat))
+			})
+			.map(|(path, _)| path.clone())
+			.collect();
+
+		if !ready.is_empty() {
+			handled.extend(ready.iter().map(|path| (path.clone(), current[path])));
+
+			let mut batch = config.clone();
+			batch.paths = ready;
+
+			if let Err(err) = organize_with(batch, fs) {
+				match config.format {
+					Format::Human => eprintln!("{}", format!("Error: {err}").red()),
+					Format::Json => eprintln!("{}", err.to_json()),
+				}
+			}
+		}
+
+		previous = current;
+		std::thread::sleep(interval);
+	}
+}
+
+pub fn organize_with(mut config: Config, fs: &dyn FileSystem) -> Result<Summary> {
+	if config.require_marker && !fs.exists(&config.destination.join(MARKER)) {
+		return Err(Error::MissingMarker(config.destination));
+	}
+
+	config.destination = fs.canonicalize(config.as_ref())?;
+
+	for source in &config.source {
+		let Ok(source) = fs.canonicalize(source) else {
+			continue;
+		};
+
+		if config.destination.starts_with(&source) {
+			return Err(Error::DestinationInsideSource(config.destination, source));
+		}
+	}
+	config.overrides = [
+		(&config.image_ext, Extension::Image),
+		(&config.video_ext, Extension::Video),
+		(&config.audio_ext, Extension::Audio),
+	]
+	.into_iter()
+	.flat_map(|(extensions, category)| {
+		extensions
+			.iter()
+			.map(move |extension| (extension.clone(), category.clone()))
+	})
+	.chain(config.map.iter().cloned())
+	.collect();
+
+	if let Some(structure) = config.dest_structure {
+		config.template = Template::from(structure);
+	}
+
+	if cfg!(not(unix)) && (config.dir_mode.is_some() || config.file_mode.is_some()) {
+		eprintln!(
+			"{}",
+			"Warning: --dir-mode and --file-mode are only supported on Unix and are ignored"
+				.yellow()
+		);
+	}
+
+	if config.simulate_tree || config.stats_only || config.against.is_some() {
+		config.dry_run = true;
+	}
+
+	let started = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs() as i64;
+
+	if config.since_last_run {
+		config.last_run = last_run(&config.destination, fs)?;
+	}
+
+	let mut context = Context::new(&config, fs)?;
+	let entries = match listed_paths(&config, fs)? {
+		Some(paths) => paths
+			.iter()
+			.flat_map(|path| {
+				let walk = Walk {
+					follow_links: config.follow_symlinks,
+					max_depth: 0,
+					..Walk::default()
+				};
+				fs.walk(path, walk, &mut |entry| {
+					!config
+						.exclude
+						.iter()
+						.any(|glob| glob.matches(entry.path(), entry.is_dir()))
+				})
+			})
+			.collect(),
+		None => config
+			.source
+			.iter()
+			.flat_map(|source| walk_source(source, &config, fs))
+			.collect(),
+	};
+	let (entries, unreadable) = readable(entries)?;
+	let (entries, remaining) = match config.max_files {
+		Some(max) => limit(entries, max, &config, fs),
+		None => (entries, 0),
+	};
+
+	if let (Mode::Copy | Mode::Move, false) = (context.mode, config.dry_run) {
+		preflight(&entries, context.mode, &config, fs)?;
+	}
+
+	if config.pair_live_photos {
+		context.live_videos = entries
+			.iter()
+			.map(DirEntry::path)
+			.filter(|path| {
+				companion(fs, path, LIVE_VIDEO_EXTENSIONS, LIVE_IMAGE_EXTENSIONS).is_some()
+			})
+			.map(Path::to_path_buf)
+			.collect();
+	}
+
+	if config.apple_edited {
+		let pairs = apple_edited(&entries, &config, fs);
+		context.apple_originals = pairs.values().cloned().collect();
+		context.apple_edited = pairs
+			.into_iter()
+			.filter_map(|(edited, original)| {
+				let original = Target::try_from((original.as_path(), &config, fs)).ok()?;
+				Some((edited, original.mtime))
+			})
+			.collect();
+	}
+
+	let pool = rayon::ThreadPoolBuilder::new()
+		.num_threads(config.threads)
+		.build()?;
+
+	if config.group_bursts {
+		context.bursts = pool.install(|| bursts(&entries, &context));
+	}
+
+	if config.dedup {
+		context.dedup_candidates = pool.install(|| dedup_candidates(&entries, &context));
+	}
+
+	context.progress = Progress::new(entries.len());
+	let summary = Summary::default();
+
+	for path in unreadable {
+		let mut output = Output::new(context.level, config.format);
+		let action = report(&Error::Permission(path.clone()), &config, &mut output);
+		output.flush();
+
+		match (config.strict, action) {
+			(true, Some(Action::Failed)) => return Err(Error::Strict(path)),
+			(_, Some(action)) => summary.record(action),
+			(_, None) => {}
+		}
+	}
+
+	let handle = |entry: &DirEntry| {
+		let mut output = Output::new(context.level, config.format);
+
+		let action = match process(entry, &context, &mut output) {
+			Ok(action) => Some(action),
+			Err(err) => {
+				let action = report(&err, &config, &mut output);
+
+				match (&config.on_skip, &err) {
+					(
+						OnSkip::MoveTo(dir),
+						Error::Skipping(_) | Error::Mime(_) | Error::Corrupt(_),
+					) => quarantine(entry.path(), dir, &context, &mut output),
+					(OnSkip::Leave, Error::Corrupt(_)) => {
+						let dir = config.destination.join("corrupt");
+						quarantine(entry.path(), &dir, &context, &mut output)
+					}
+					_ => {}
+				}
+
+				action
+			}
+		};
+
+		if let Some(action) = action {
+			summary.record(action);
+
+			if let (true, Action::Moved | Action::Copied | Action::Linked) =
+				(config.simulate_tree, action)
+			{
+				if let Some(dir) = output.record.destination.as_deref().and_then(Path::parent) {
+					context.tree.lock().unwrap().add(dir);
+				}
+			}
+
+			output.record.source = entry.path().to_path_buf();
+			output.record.action = Some(action);
+
+			if let Format::Json = config.format {
+				output.stdout = vec![Value::from(&output.record).to_string()];
+			}
+
+			if config.report.is_some() {
+				let record = std::mem::take(&mut output.record);
+				context.records.lock().unwrap().push(record);
+			}
+		}
+
+		if config.quiet || config.simulate_tree || config.stats_only {
+			output.stdout.clear();
+			output.planned.clear();
+		}
+
+		context
+			.plan
+			.lock()
+			.unwrap()
+			.extend(std::mem::take(&mut output.planned));
+
+		context.progress.finish(output);
+
+		match (config.strict, action) {
+			(true, Some(Action::Failed)) => Err(Error::Strict(entry.path().to_path_buf())),
+			_ => Ok(()),
+		}
+	};
+	let (live_videos, rest): (Vec<_>, Vec<_>) = entries
+		.iter()
+		.partition(|entry| context.live_videos.contains(entry.path()));
+
+	let result = pool.install(|| {
+		rest.par_iter()
+			.try_for_each(|entry| handle(entry))
+			.and_then(|_| live_videos.par_iter().try_for_each(|entry| handle(entry)))
+	});
+
+	context.progress.clear();
+
+	let mut output = Output::new(context.level, config.format);
+
+	for (_, (writer, archived)) in context.archives.lock().unwrap().drain() {
+		writer.finish()?;
+
+		for (source, dest_file) in archived {
+			if let Err(err) = context.journal(&source, &dest_file) {
+				output.log(Level::Warn, format!("Could not write journal: {err}"));
+			}
+
+			if let Mode::Move = context.mode {
+				context.fs.remove_file(&source)?;
+			}
+		}
+	}
+
+	output.flush();
+
+	if config.move_empty_dirs && !config.dry_run && matches!(context.mode, Mode::Move) {
+		let mut output = Output::new(context.level, config.format);
+		remove_empty_dirs(&config, &entries, fs, &mut output);
+		output.flush();
+	}
+
+	if config.simulate_tree {
+		print!("{}", context.tree.lock().unwrap());
+	}
+
+	print!("{}", context.plan.lock().unwrap());
+
+	if config.stats || config.stats_only {
+		print!("{}", context.stats.lock().unwrap());
+	}
+
+	if let (Some(_), Format::Human) = (&config.against, config.format) {
+		print!("{}", context.diff.lock().unwrap());
+	}
+
+	match (config.format, remaining) {
+		(Format::Human, remaining) if remaining > 0 && context.level >= Level::Info => println!(
+			"Stopped after {} files, {remaining} remaining",
+			config.max_files.unwrap_or_default()
+		),
+		_ => {}
+	}
+
+	match (config.format, config.dry_run) {
+		_ if context.level < Level::Info || config.stats_only => {}
+		(Format::Json, _) => println!("{}", Value::from(&summary)),
+		(Format::Human, true) => println!("[dry-run] {summary}"),
+		(Format::Human, false) => println!("{summary}"),
+	}
+
+	if let (Some(path), Some(checksums)) = (&config.checksum_db, &context.checksums) {
+		checksums.save(path, fs)?;
+	}
+
+	if let Some(report) = &config.report {
+		let records = context.records.into_inner().unwrap();
+		let report_json = Value::Array(records.iter().map(Value::from).collect());
+		fs.write(report, format!("{report_json}\n").as_bytes())?;
+	}
+
+	result?;
+
+	if let (true, false, 0) = (config.since_last_run, config.dry_run, summary.failed()) {
+		save_last_run(&config.destination, started, fs)?;
+	}
+
+	Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+	use fs::MemoryFileSystem;
+	use std::path::Component;
+
+	#[derive(Parser)]
+	struct Cli {
+		#[command(flatten)]
+		config: Config,
+	}
+
+	fn config(args: &[&str]) -> Config {
+		Cli::parse_from(["tf"].iter().chain(args)).config
+	}
+
+	const MODIFIED: i64 = 1_700_000_000;
+
+	fn jpeg(model: &[u8; 4]) -> Vec<u8> {
+		let mut tiff = b"II\x2a\x00\x08\x00\x00\x00\x01\x00".to_vec();
+		tiff.extend([0x10, 0x01, 0x02, 0x00, 0x03, 0x00, 0x00, 0x00]);
+		tiff.extend(model);
+		tiff.extend([0; 4]);
+
+		let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+		jpeg.extend((tiff.len() as u16 + 8).to_be_bytes());
+		jpeg.extend(b"Exif\0\0");
+		jpeg.extend(tiff);
+		jpeg.extend([0xFF, 0xD9]);
+		jpeg
+	}
+
+	fn iso_box(name: &[u8; 4], body: &[u8]) -> Vec<u8> {
+		let mut iso_box = (body.len() as u32 + 8).to_be_bytes().to_vec();
+		iso_box.extend(name);
+		iso_box.extend(body);
+		iso_box
+	}
+
+	fn heic(date: &str) -> Vec<u8> {
+		let mut tiff = b"II\x2a\x00\x08\x00\x00\x00\x01\x00".to_vec();
+		tiff.extend([
+			0x69, 0x87, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1A, 0x00, 0x00, 0x00,
+		]);
+		tiff.extend([0; 4]);
+		tiff.extend([0x01, 0x00, 0x03, 0x90, 0x02, 0x00, 0x14, 0x00, 0x00, 0x00]);
+		tiff.extend([0x2C, 0x00, 0x00, 0x00]);
+		tiff.extend([0; 4]);
+		tiff.extend(date.as_bytes());
+		tiff.push(0);
+
+		let item = [&[0, 0, 0, 6][..], b"Exif\0\0", &tiff].concat();
+		let infe = iso_box(b"infe", &[&[2, 0, 0, 0, 0, 1, 0, 0][..], b"Exif"].concat());
+		let iinf = iso_box(b"iinf", &[&[0, 0, 0, 0, 0, 1][..], &infe].concat());
+		let iloc = |offset: u32| {
+			let mut body = vec![0, 0, 0, 0, 0x44, 0x00, 0, 1, 0, 1, 0, 0, 0, 1];
+			body.extend(offset.to_be_bytes());
+			body.extend((item.len() as u32).to_be_bytes());
+			iso_box(b"iloc", &body)
+		};
+
+		let ftyp = iso_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+		let meta = |offset| iso_box(b"meta", &[&[0, 0, 0, 0][..], &iinf, &iloc(offset)].concat());
+		let offset = (ftyp.len() + meta(0).len() + 8) as u32;
+
+		[ftyp, meta(offset), iso_box(b"mdat", &item)].concat()
+	}
+
+	#[test]
+	fn sanitize_replaces_leading_dots() {
+		assert_eq!(sanitize("."), "_");
+		assert_eq!(sanitize(".."), "__");
+		assert_eq!(sanitize(" ../etc "), "___etc");
+		assert_eq!(sanitize("Canon EOS 5D"), "Canon EOS 5D");
+	}
+
+	#[test]
+	fn camera_model_cannot_escape_destination() {
+		let fs = MemoryFileSystem::new();
+		let photo = Path::new("/source/photo.jpg");
+		fs.add_file(photo, &jpeg(b"..\0\0"), MODIFIED);
+
+		let config = config(&["-s", "/source", "-d", "/destination", "--group-by-camera"]);
+		let entry = fs
+			.walk(photo, Walk::default(), &mut |_| true)
+			.remove(0)
+			.unwrap();
+		let target = Target::try_from((&entry, &config, &fs as &dyn FileSystem)).unwrap();
+		let dest_dir = dest_dir(&entry, &target, "", &config);
+
+		assert_eq!(target.camera.as_deref(), Some("__"));
+		assert!(dest_dir.starts_with("/destination"));
+		assert!(!dest_dir
+			.components()
+			.any(|component| component == Component::ParentDir));
+	}
+
+	fn files(fs: &dyn FileSystem, root: &Path) -> Vec<PathBuf> {
+		fs.walk(root, Walk::default(), &mut |_| true)
+			.into_iter()
+			.filter_map(|entry| entry.ok())
+			.filter(|entry| !entry.is_dir())
+			.map(DirEntry::into_path)
+			.collect()
+	}
+
+	#[test]
+	fn conflict_keeps_existing_destination() {
+		let fs = MemoryFileSystem::new();
+		fs.add_file(Path::new("/source/photo.jpg"), b"new", MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&["-s", "/source", "-d", "/destination", "-c"]);
+		organize_with(config.clone(), &fs).unwrap();
+
+		let [placed] = files(&fs, Path::new("/destination")).try_into().unwrap();
+		fs.add_file(&placed, b"old", MODIFIED);
+
+		let summary = organize_with(config, &fs).unwrap();
+
+		assert_eq!(summary.skipped(), 1);
+		assert_eq!(fs.contents(&placed).as_deref(), Some(&b"old"[..]));
+		assert_eq!(
+			fs.contents(Path::new("/source/photo.jpg")).as_deref(),
+			Some(&b"new"[..])
+		);
+	}
+
+	#[test]
+	fn dedup_places_identical_files_once() {
+		let fs = MemoryFileSystem::new();
+		fs.add_file(Path::new("/source/a.jpg"), b"same", MODIFIED);
+		fs.add_file(Path::new("/source/b.jpg"), b"same", MODIFIED);
+		fs.add_file(Path::new("/source/c.jpg"), b"other", MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&["-s", "/source", "-d", "/destination", "-c", "--dedup"]);
+		let summary = organize_with(config, &fs).unwrap();
+
+		assert_eq!(summary.copied(), 2);
+		assert_eq!(files(&fs, Path::new("/destination")).len(), 2);
+	}
+
+	#[test]
+	fn move_across_devices_copies_and_removes_source() {
+		let fs = MemoryFileSystem::new().with_device(Path::new("/destination"));
+		fs.add_file(Path::new("/source/photo.jpg"), b"photo", MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&["-s", "/source", "-d", "/destination"]);
+		let summary = organize_with(config, &fs).unwrap();
+		let [placed] = files(&fs, Path::new("/destination")).try_into().unwrap();
+
+		assert_eq!(summary.moved(), 1);
+		assert_eq!(fs.contents(&placed).as_deref(), Some(&b"photo"[..]));
+		assert!(!fs.exists(Path::new("/source/photo.jpg")));
+	}
+
+	#[test]
+	fn live_video_follows_heic_exif_date() {
+		let fs = MemoryFileSystem::new();
+		fs.add_file(
+			Path::new("/source/IMG_0001.HEIC"),
+			&heic("2019:06:07 08:09:10"),
+			MODIFIED,
+		);
+		fs.add_file(Path::new("/source/IMG_0001.MOV"), b"video", MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&["-s", "/source", "-d", "/destination", "--pair-live-photos"]);
+		let summary = organize_with(config, &fs).unwrap();
+
+		assert_eq!(summary.moved(), 1);
+		assert_eq!(
+			files(&fs, Path::new("/destination")),
+			[
+				Path::new("/destination/pictures/2019/june/IMG_0001.HEIC"),
+				Path::new("/destination/pictures/2019/june/IMG_0001.MOV"),
+			]
+		);
+	}
+
+	#[test]
+	fn rename_on_move_numbers_each_directory() {
+		let fs = MemoryFileSystem::new();
+		fs.add_file(Path::new("/source/a.jpg"), b"a", 1_690_000_000);
+		fs.add_file(Path::new("/source/b.jpg"), b"b", 1_690_000_000);
+		fs.add_file(Path::new("/source/c.jpg"), b"c", MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&[
+			"-s",
+			"/source",
+			"-d",
+			"/destination",
+			"--threads",
+			"1",
+			"--rename-on-move",
+			"{orig}-{seq}.{ext}",
+		]);
+		organize_with(config, &fs).unwrap();
+
+		assert_eq!(
+			files(&fs, Path::new("/destination")),
+			[
+				Path::new("/destination/pictures/2023/july/a-0001.jpg"),
+				Path::new("/destination/pictures/2023/july/b-0002.jpg"),
+				Path::new("/destination/pictures/2023/november/c-0001.jpg"),
+			]
+		);
+	}
+
+	#[test]
+	fn rename_on_move_rejects_empty_names() {
+		let fs = MemoryFileSystem::new();
+		fs.add_file(Path::new("/source/photo"), &jpeg(b"EOS\0"), MODIFIED);
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let rename = |template: &str| {
+			let args = [
+				"-s",
+				"/source",
+				"-d",
+				"/destination",
+				"-c",
+				"--rename-on-move",
+				template,
+			];
+			organize_with(config(&args), &fs).unwrap()
+		};
+
+		assert_eq!(rename("{ext}").failed(), 1);
+		assert!(files(&fs, Path::new("/destination")).is_empty());
+
+		assert_eq!(rename("{orig}.{ext}").copied(), 1);
+		assert_eq!(
+			files(&fs, Path::new("/destination")),
+			[Path::new("/destination/pictures/2023/november/photo")]
+		);
+	}
+
+	#[test]
+	fn max_files_takes_first_files_in_walk_order() {
+		let fs = MemoryFileSystem::new();
+
+		for name in ["a.jpg", "b.txt", "c.jpg", "d.jpg"] {
+			fs.add_file(&Path::new("/source").join(name), name.as_bytes(), MODIFIED);
+		}
+
+		fs.create_dir_all(Path::new("/destination")).unwrap();
+
+		let config = config(&[
+			"-s",
+			"/source",
+			"-d",
+			"/destination",
+			"-c",
+			"--max-files",
+			"2",
+		]);
+		organize_with(config, &fs).unwrap();
+
+		let names: Vec<_> = files(&fs, Path::new("/destination"))
+			.iter()
+			.filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+			.collect();
+
+		assert_eq!(names, ["a.jpg", "c.jpg"]);
+	}
+}
@@ -0,0 +1,205 @@
+const LOCALES: [(&str, [&str; 12]); 9] = [
+	(
+		"en",
+		[
+			"january",
+			"february",
+			"march",
+			"april",
+			"may",
+			"june",
+			"july",
+			"august",
+			"september",
+			"october",
+			"november",
+			"december",
+		],
+	),
+	(
+		"de",
+		[
+			"januar",
+			"februar",
+			"märz",
+			"april",
+			"mai",
+			"juni",
+			"juli",
+			"august",
+			"september",
+			"oktober",
+			"november",
+			"dezember",
+		],
+	),
+	(
+		"es",
+		[
+			"enero",
+			"febrero",
+			"marzo",
+			"abril",
+			"mayo",
+			"junio",
+			"julio",
+			"agosto",
+			"septiembre",
+			"octubre",
+			"noviembre",
+			"diciembre",
+		],
+	),
+	(
+		"fr",
+		[
+			"janvier",
+			"février",
+			"mars",
+			"avril",
+			"mai",
+			"juin",
+			"juillet",
+			"août",
+			"septembre",
+			"octobre",
+			"novembre",
+			"décembre",
+		],
+	),
+	(
+		"it",
+		[
+			"gennaio",
+			"febbraio",
+			"marzo",
+			"aprile",
+			"maggio",
+			"giugno",
+			"luglio",
+			"agosto",
+			"settembre",
+			"ottobre",
+			"novembre",
+			"dicembre",
+		],
+	),
+	(
+		"mk",
+		[
+			"јануари",
+			"февруари",
+			"март",
+			"април",
+			"мај",
+			"јуни",
+			"јули",
+			"август",
+			"септември",
+			"октомври",
+			"ноември",
+			"декември",
+		],
+	),
+	(
+		"nl",
+		[
+			"januari",
+			"februari",
+			"maart",
+			"april",
+			"mei",
+			"juni",
+			"juli",
+			"augustus",
+			"september",
+			"oktober",
+			"november",
+			"december",
+		],
+	),
+	(
+		"pt",
+		[
+			"janeiro",
+			"fevereiro",
+			"março",
+			"abril",
+			"maio",
+			"junho",
+			"julho",
+			"agosto",
+			"setembro",
+			"outubro",
+			"novembro",
+			"dezembro",
+		],
+	),
+	(
+		"sr",
+		[
+			"јануар",
+			"фебруар",
+			"март",
+			"април",
+			"мај",
+			"јун",
+			"јул",
+			"август",
+			"септембар",
+			"октобар",
+			"новембар",
+			"децембар",
+		],
+	),
+];
+
+#[derive(Clone, Copy)]
+pub struct Locale(&'static [&'static str; 12]);
+
+impl Locale {
+	pub fn parse(locale: &str) -> Result<Self, String> {
+		let language = locale
+			.split(['_', '-', '.'])
+			.next()
+			.unwrap_or_default()
+			.to_lowercase();
+
+		LOCALES
+			.iter()
+			.find(|(code, _)| *code == language)
+			.map(|(_, months)| Self(months))
+			.ok_or_else(|| {
+				let supported: Vec<_> = LOCALES.iter().map(|(code, _)| *code).collect();
+				format!(
+					"Unsupported locale {locale}, expected one of {}",
+					supported.join(", ")
+				)
+			})
+	}
+
+	pub fn month(&self, month0: u32) -> &'static str {
+		self.0[month0 as usize]
+	}
+}
+
+pub fn parse_month(month: &str) -> Option<u32> {
+	let month = month.to_lowercase();
+
+	if let Some(number) = month
+		.parse()
+		.ok()
+		.filter(|number| (1..=12).contains(number))
+	{
+		return Some(number);
+	}
+
+	if let Some((number, name)) = month.split_once('-') {
+		return parse_month(number).filter(|number| parse_month(name) == Some(*number));
+	}
+
+	LOCALES.iter().find_map(|(_, months)| {
+		let month0 = months.iter().position(|name| *name == month)?;
+		Some(month0 as u32 + 1)
+	})
+}
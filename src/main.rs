@@ -1,179 +1,184 @@
-use chrono::DateTime;
-use chrono::Datelike;
+mod completions;
+mod config;
+
+use clap::Arg;
+use clap::CommandFactory;
+use clap::FromArgMatches;
 use clap::Parser;
-use filetime::FileTime;
-use std::io;
-use std::path::Path;
+use clap::Subcommand;
+use colored::Colorize;
+use completions::Shell;
+use config::ConfigFile;
 use std::path::PathBuf;
-use thiserror::Error;
-use walkdir::WalkDir;
+use std::process::ExitCode;
+use std::time::Duration;
+use tf::fs::RealFileSystem;
+use tf::Capability;
+use tf::Color;
+use tf::Config;
+use tf::Error;
+use tf::Extension;
+use tf::Format;
+use tf::Result;
+use tf::Timezone;
 
-type Result<T> = std::result::Result<T, Error>;
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Tf {
+	#[command(flatten)]
+	config: Option<Config>,
 
-#[derive(Error, Debug)]
-enum Error<P = PathBuf> {
-	#[error("Walkdir error: {0}")]
-	WalkDir(#[from] walkdir::Error),
+	#[command(subcommand)]
+	command: Option<Command>,
 
-	#[error("IO error: {0}")]
-	Io(#[from] io::Error),
+	#[arg(long, global = true, value_enum, default_value_t = Format::Human)]
+	format: Format,
 
-	#[error("Skipping file: {0}")]
-	Skipping(P),
+	#[arg(long, global = true, value_enum, default_value_t = Color::Auto)]
+	color: Color,
+}
 
-	#[error("File has no name")]
-	NoName(P),
+#[derive(Subcommand)]
+enum Command {
+	Init {
+		dir: PathBuf,
+	},
 
-	#[error("DateTime error")]
-	DateTime(P),
+	Undo {
+		journal: PathBuf,
+	},
 
-	#[error("Mime error")]
-	Mime(P),
+	Prune {
+		#[arg(long)]
+		journal: PathBuf,
 
-	#[error("{0} is a directory")]
-	Dir(P),
-}
+		#[arg(long, value_parser = parse_days)]
+		older_than: Duration,
 
-#[derive(Parser)]
-struct Cli {
-	#[arg(short, long, required = true)]
-	source: PathBuf,
+		#[arg(long)]
+		dry_run: bool,
+	},
 
-	#[arg(short, long, required = true)]
-	destination: PathBuf,
+	VerifyArchive {
+		#[arg(long)]
+		root: PathBuf,
 
-	#[arg(short, long, required = true)]
-	person: String,
+		#[arg(long, default_value = "local", allow_hyphen_values = true, value_parser = Timezone::parse)]
+		timezone: Timezone,
+	},
 
-	#[arg(short = 'y', long, default_value = "false")]
-	dry_run: Option<bool>,
-}
+	Doctor {
+		#[arg(long, value_enum, value_delimiter = ',')]
+		require: Vec<Capability>,
+	},
 
-impl AsRef<Path> for Cli {
-	fn as_ref(&self) -> &Path {
-		&self.destination
-	}
+	#[command(hide = true)]
+	Completions {
+		shell: Shell,
+	},
 }
 
-struct Target {
-	abs_path: PathBuf,
-	extension: Extension,
-	mtime: MTime,
-	name: String,
+fn parse_days(days: &str) -> std::result::Result<Duration, String> {
+	let days: u64 = days
+		.trim()
+		.parse()
+		.map_err(|_| format!("Invalid number of days {days}"))?;
+
+	match days.checked_mul(24 * 60 * 60) {
+		Some(secs) => Ok(Duration::from_secs(secs)),
+		None => Err(format!("{days} days is too long")),
+	}
 }
 
-impl TryFrom<&Path> for Target {
-	type Error = Error;
+fn config_path() -> Option<PathBuf> {
+	let mut args = std::env::args_os().skip(1);
 
-	fn try_from(path: &Path) -> Result<Self> {
-		if path.is_dir() {
-			return Err(Error::Dir(path.to_path_buf()));
+	while let Some(arg) = args.next() {
+		if arg == "--config" {
+			return args.next().map(PathBuf::from);
 		}
 
-		let abs_path = std::fs::canonicalize(path)?;
-		let extension = Extension::try_from(&abs_path)?;
-		let name = abs_path
-			.file_name()
-			.ok_or(Error::NoName(path.to_path_buf()))?
-			.to_str()
-			.ok_or(Error::NoName(path.to_path_buf()))?
-			.to_string();
-		let mtime = MTime::try_from(&abs_path)?;
-
-		Ok(Self { abs_path, name, extension, mtime })
+		if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+			return Some(PathBuf::from(path));
+		}
 	}
-}
 
-struct MTime {
-	year: String,
-	month: String,
+	let default = PathBuf::from("tf.toml");
+	default.exists().then_some(default)
 }
 
-impl TryFrom<&PathBuf> for MTime {
-	type Error = Error;
+fn run(format: &mut Format) -> Result<()> {
+	let mut command = Tf::command().arg(Arg::new("config").long("config").value_name("CONFIG"));
+	let mut map = Vec::new();
+
+	if let Some(path) = config_path() {
+		let file = ConfigFile::read(&path).map_err(|err| Error::Config(path, err))?;
+		map = file.map().to_vec();
+		command = file.apply(command);
+	}
 
-	fn try_from(path: &PathBuf) -> Result<Self> {
-		let metadata = std::fs::metadata(path)?;
-		let filetime = FileTime::from_last_modification_time(&metadata);
-		let secs = filetime.seconds();
-		let date = DateTime::from_timestamp(secs, 0).ok_or(Error::DateTime(path.clone()))?;
-		let month = date.format("%B").to_string().to_lowercase();
-		let year = date.year().to_string();
+	let matches = command.clone().get_matches();
+	let tf = Tf::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+	*format = tf.format;
+	tf.color.apply();
 
-		Ok(Self { year, month })
+	match (tf.command, tf.config) {
+		(Some(Command::Init { dir }), _) => tf::init(&dir, &RealFileSystem),
+		(Some(Command::Undo { journal }), _) => tf::undo(&journal, &RealFileSystem),
+		(Some(Command::Prune { journal, older_than, dry_run }), _) => {
+			tf::prune(&journal, older_than, dry_run, &RealFileSystem)
+		}
+		(Some(Command::VerifyArchive { root, timezone }), _) => {
+			install(&timezone);
+			tf::verify_archive(&root, &timezone, &RealFileSystem).map(|_| ())
+		}
+		(Some(Command::Doctor { require }), _) => tf::doctor(&require),
+		(Some(Command::Completions { shell }), _) => {
+			print!("{}", completions::generate(shell, &command));
+			Ok(())
+		}
+		(None, Some(config)) => organize(config, map, tf.format),
+		(None, None) => organize(
+			Config::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()),
+			map,
+			tf.format,
+		),
 	}
 }
 
-#[derive(Clone)]
-enum Extension {
-	Image,
-	Video,
+fn install(timezone: &Timezone) {
+	if let Timezone::Named(name) = timezone {
+		std::env::set_var("TZ", name);
+	}
 }
 
-impl TryFrom<&PathBuf> for Extension {
-	type Error = Error;
-
-	fn try_from(path: &PathBuf) -> Result<Self> {
-		let extension = path
-			.extension()
-			.ok_or(Error::Skipping(path.clone()))?
-			.to_str()
-			.ok_or(Error::Skipping(path.clone()))?;
-
-		let mime = mime_guess::from_ext(extension)
-			.first()
-			.ok_or(Error::Mime(path.clone()))?
-			.to_string();
-
-		let extension = match mime {
-			ext if ext.starts_with("image") => Extension::Image,
-			ext if ext.starts_with("video") => Extension::Video,
-			_ => return Err(Error::Skipping(path.clone())),
-		};
+fn organize(mut config: Config, map: Vec<(String, Extension)>, format: Format) -> Result<()> {
+	config.map.splice(0..0, map);
+	config.format = format;
+	install(&config.timezone);
 
-		Ok(extension)
+	if config.watch {
+		return tf::watch(config, &RealFileSystem);
 	}
-}
 
-impl ToString for Extension {
-	fn to_string(&self) -> String {
-		match self {
-			Extension::Video => "videos".to_owned(),
-			Extension::Image => "pictures".to_owned(),
-		}
+	match tf::organize(config)?.failed() {
+		0 => Ok(()),
+		failed => Err(Error::Failed(failed)),
 	}
 }
 
-fn main() -> Result<()> {
-	let mut cli = Cli::parse();
-	cli.destination = std::fs::canonicalize(&cli)?;
-
-	for item in WalkDir::new(cli.source) {
-		let item = item?;
-		let path = item.path();
+fn main() -> ExitCode {
+	let mut format = Format::Human;
 
-		let target = match Target::try_from(path) {
-			Ok(target) => target,
-			Err(err) => {
-				eprintln!("Error: {:#?}", err);
-				continue;
+	match run(&mut format) {
+		Ok(_) => ExitCode::SUCCESS,
+		Err(err) => {
+			match format {
+				Format::Human => eprintln!("{}", format!("Error: {err}").red()),
+				Format::Json => eprintln!("{}", err.to_json()),
 			}
-		};
-
-		let destination = cli
-			.destination
-			.join(target.extension.to_string())
-			.join(&cli.person)
-			.join(target.mtime.year)
-			.join(target.mtime.month)
-			.join(target.name);
-
-		if !cli.dry_run.unwrap() {
-			// TODO: move files
-		}
 
-		println!("{} -> {}", target.abs_path.display(), destination.display());
+			ExitCode::FAILURE
+		}
 	}
-
-	Ok(())
 }
@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct Plan {
+	dirs: BTreeMap<PathBuf, Vec<(PathBuf, PathBuf)>>,
+}
+
+impl Plan {
+	pub fn add(&mut self, from: PathBuf, to: PathBuf) {
+		let dir = to.parent().map(Path::to_path_buf).unwrap_or_default();
+		self.dirs.entry(dir).or_default().push((to, from));
+	}
+}
+
+impl Extend<(PathBuf, PathBuf)> for Plan {
+	fn extend<T: IntoIterator<Item = (PathBuf, PathBuf)>>(&mut self, iter: T) {
+		for (from, to) in iter {
+			self.add(from, to);
+		}
+	}
+}
+
+impl Display for Plan {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		for (dir, files) in &self.dirs {
+			let label = match files.len() {
+				1 => "1 file".to_string(),
+				count => format!("{count} files"),
+			};
+
+			writeln!(f, "[dry-run] {} ({label})", dir.display())?;
+
+			let mut files: Vec<_> = files.iter().collect();
+			files.sort();
+
+			for (to, from) in files {
+				let name = to.file_name().unwrap_or_default().to_string_lossy();
+				writeln!(f, "  {} -> {name}", from.display())?;
+			}
+		}
+
+		Ok(())
+	}
+}
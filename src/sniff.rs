@@ -0,0 +1,81 @@
+use crate::fs::FileSystem;
+use crate::Extension;
+use std::io::Read;
+use std::path::Path;
+
+const HEAD: u64 = 4 * 1024;
+
+const MAGIC: [(&[u8], Extension); 11] = [
+	(b"\xFF\xD8\xFF", Extension::Image),
+	(b"\x89PNG\r\n\x1A\n", Extension::Image),
+	(b"GIF87a", Extension::Image),
+	(b"GIF89a", Extension::Image),
+	(b"II*\x00", Extension::Image),
+	(b"MM\x00*", Extension::Image),
+	(b"\x1A\x45\xDF\xA3", Extension::Video),
+	(b"ID3", Extension::Audio),
+	(b"\xFF\xFB", Extension::Audio),
+	(b"fLaC", Extension::Audio),
+	(b"OggS", Extension::Audio),
+];
+
+const RIFF: [(&[u8], Extension); 3] = [
+	(b"WEBP", Extension::Image),
+	(b"AVI ", Extension::Video),
+	(b"WAVE", Extension::Audio),
+];
+
+const FTYP: [(&[u8], Extension); 12] = [
+	(b"heic", Extension::Image),
+	(b"heix", Extension::Image),
+	(b"mif1", Extension::Image),
+	(b"avif", Extension::Image),
+	(b"isom", Extension::Video),
+	(b"iso2", Extension::Video),
+	(b"mp41", Extension::Video),
+	(b"mp42", Extension::Video),
+	(b"avc1", Extension::Video),
+	(b"qt  ", Extension::Video),
+	(b"M4V ", Extension::Video),
+	(b"M4A ", Extension::Audio),
+];
+
+const VALIDATED: &[&str] = &[
+	"jpg", "jpeg", "png", "gif", "tif", "tiff", "webp", "heic", "heif", "avif", "mp4", "m4v",
+	"mov", "mkv", "webm", "avi", "wav", "flac", "ogg", "m4a",
+];
+
+fn head(fs: &dyn FileSystem, path: &Path) -> Option<Vec<u8>> {
+	let mut head = Vec::new();
+	fs.open(path).ok()?.take(HEAD).read_to_end(&mut head).ok()?;
+	Some(head)
+}
+
+pub fn sniff(fs: &dyn FileSystem, path: &Path) -> Option<Extension> {
+	let head = head(fs, path)?;
+
+	if let Some((_, extension)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+		return Some(extension.clone());
+	}
+
+	let (kind, brand) = match head.get(..12)? {
+		[b'R', b'I', b'F', b'F', _, _, _, _, kind @ ..] => (RIFF.as_slice(), kind),
+		[_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] => (FTYP.as_slice(), brand),
+		_ => return None,
+	};
+
+	kind.iter()
+		.find(|(magic, _)| *magic == brand)
+		.map(|(_, extension)| extension.clone())
+}
+
+pub fn valid(fs: &dyn FileSystem, path: &Path) -> bool {
+	let validated = path
+		.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| VALIDATED.contains(&extension.to_lowercase().as_str()));
+
+	!validated
+		|| sniff(fs, path).is_some()
+		|| head(fs, path).is_some_and(|head| head.get(4..8) == Some(b"ftyp"))
+}
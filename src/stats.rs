@@ -0,0 +1,55 @@
+use crate::Extension;
+use crate::Target;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Default)]
+pub struct Stats {
+	kinds: BTreeMap<Extension, usize>,
+	years: BTreeMap<String, usize>,
+}
+
+impl Stats {
+	pub fn add(&mut self, target: &Target) {
+		*self.kinds.entry(target.extension.clone()).or_default() += 1;
+		*self.years.entry(target.mtime.year.clone()).or_default() += 1;
+	}
+}
+
+fn table(f: &mut Formatter, header: &str, rows: &[(String, usize)]) -> fmt::Result {
+	let width = rows
+		.iter()
+		.map(|(key, _)| key.chars().count())
+		.chain([header.len()])
+		.max()
+		.unwrap_or_default();
+
+	writeln!(f, "{header:<width$}  files")?;
+
+	for (key, count) in rows {
+		writeln!(f, "{key:<width$}  {count:>5}")?;
+	}
+
+	Ok(())
+}
+
+impl Display for Stats {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let kinds: Vec<_> = self
+			.kinds
+			.iter()
+			.map(|(kind, count)| (kind.to_string(), *count))
+			.collect();
+		let years: Vec<_> = self
+			.years
+			.iter()
+			.map(|(year, count)| (year.clone(), *count))
+			.collect();
+
+		table(f, "kind", &kinds)?;
+		writeln!(f)?;
+		table(f, "year", &years)
+	}
+}
@@ -0,0 +1,162 @@
+use crate::Target;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Clone)]
+enum Segment {
+	Literal(String),
+	Kind,
+	Person,
+	Year,
+	Month,
+	MonthNumber,
+	Day,
+	Hour,
+	Minute,
+	Second,
+	Hms,
+	Seq,
+	Orig,
+	Ext,
+}
+
+#[derive(Clone)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+	pub fn parse(template: &str) -> Result<Self, String> {
+		Self::parse_with(template, false)
+	}
+
+	fn parse_with(template: &str, file: bool) -> Result<Self, String> {
+		let mut segments = Vec::new();
+		let mut rest = template;
+
+		while let Some(start) = rest.find('{') {
+			if start > 0 {
+				segments.push(Segment::Literal(rest[..start].to_string()));
+			}
+
+			let end = rest[start..]
+				.find('}')
+				.ok_or(format!("Unclosed placeholder in {template}"))?;
+			let segment = match &rest[start + 1..start + end] {
+				"kind" => Segment::Kind,
+				"person" => Segment::Person,
+				"year" => Segment::Year,
+				"month" => Segment::Month,
+				"month_number" => Segment::MonthNumber,
+				"day" => Segment::Day,
+				"hour" => Segment::Hour,
+				"minute" => Segment::Minute,
+				"second" => Segment::Second,
+				"hms" => Segment::Hms,
+				"seq" if file => Segment::Seq,
+				"orig" if file => Segment::Orig,
+				"ext" if file => Segment::Ext,
+				token => return Err(format!("Unknown placeholder {{{token}}}")),
+			};
+
+			segments.push(segment);
+			rest = &rest[start + end + 1..];
+		}
+
+		if !rest.is_empty() {
+			segments.push(Segment::Literal(rest.to_string()));
+		}
+
+		Ok(Self(segments))
+	}
+
+	pub fn parse_name(template: &str) -> Result<Self, String> {
+		match template.contains('/') {
+			true => Err(format!("Rename pattern {template} must not contain /")),
+			false => Self::parse(template),
+		}
+	}
+
+	pub fn parse_file_name(template: &str) -> Result<Self, String> {
+		match template.contains('/') {
+			true => Err(format!("File name template {template} must not contain /")),
+			false => Self::parse_with(template, true),
+		}
+	}
+
+	fn expand(&self, target: &Target, kind: &str, person: &str) -> String {
+		self.expand_with(target, kind, person, 0)
+	}
+
+	fn expand_with(&self, target: &Target, kind: &str, person: &str, seq: usize) -> String {
+		let name = Path::new(&target.name);
+
+		self.0.iter().fold(String::new(), |mut expanded, segment| {
+			let text = match segment {
+				Segment::Literal(text) => text.clone(),
+				Segment::Kind => kind.to_string(),
+				Segment::Person => person.to_string(),
+				Segment::Year => target.mtime.year.clone(),
+				Segment::Month => target.mtime.month.clone(),
+				Segment::MonthNumber => target.mtime.month_number.clone(),
+				Segment::Day => target.mtime.day.clone(),
+				Segment::Hour => target.mtime.date.format("%H").to_string(),
+				Segment::Minute => target.mtime.date.format("%M").to_string(),
+				Segment::Second => target.mtime.date.format("%S").to_string(),
+				Segment::Hms => target.mtime.date.format("%H%M%S").to_string(),
+				Segment::Seq => format!("{seq:04}"),
+				Segment::Orig => name
+					.file_stem()
+					.unwrap_or_default()
+					.to_string_lossy()
+					.into_owned(),
+				Segment::Ext => name
+					.extension()
+					.unwrap_or_default()
+					.to_string_lossy()
+					.into_owned(),
+			};
+
+			if text.is_empty() && matches!(segment, Segment::Ext) && expanded.ends_with('.') {
+				expanded.pop();
+			}
+
+			expanded.push_str(&text);
+			expanded
+		})
+	}
+
+	pub fn rename(&self, target: &Target, kind: &str, person: &str) -> OsString {
+		let mut name = OsString::from(self.expand(target, kind, person).replace('/', "_"));
+
+		if let Some(extension) = Path::new(&target.name).extension() {
+			name.push(".");
+			name.push(extension);
+		}
+
+		name
+	}
+
+	pub fn file_name(
+		&self,
+		target: &Target,
+		kind: &str,
+		person: &str,
+		seq: usize,
+	) -> Option<OsString> {
+		let name = self
+			.expand_with(target, kind, person, seq)
+			.replace('/', "_");
+
+		match name.trim_matches('.').is_empty() {
+			true => None,
+			false => Some(OsString::from(name)),
+		}
+	}
+
+	pub fn render(&self, target: &Target, kind: &str, person: &str) -> PathBuf {
+		self.expand(target, kind, person)
+			.split('/')
+			.filter(|component| !component.is_empty())
+			.collect()
+	}
+}
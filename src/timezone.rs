@@ -0,0 +1,67 @@
+use chrono::DateTime;
+use chrono::FixedOffset;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use std::path::Path;
+
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+#[derive(Clone)]
+pub enum Timezone {
+	Local,
+	Utc,
+	Fixed(FixedOffset),
+	Named(String),
+}
+
+impl Timezone {
+	pub fn parse(timezone: &str) -> Result<Self, String> {
+		match timezone.to_lowercase().as_str() {
+			"local" => return Ok(Self::Local),
+			"utc" | "z" => return Ok(Self::Utc),
+			_ => {}
+		}
+
+		if let Ok(offset) = timezone.parse() {
+			return Ok(Self::Fixed(offset));
+		}
+
+		let known = !timezone
+			.split('/')
+			.any(|part| part.is_empty() || part == "..")
+			&& Path::new(ZONEINFO).join(timezone).is_file();
+
+		match known {
+			true => Ok(Self::Named(timezone.to_string())),
+			false => Err(format!(
+				"Unknown timezone {timezone}, expected local, utc, an offset like +02:00 or a name from {ZONEINFO}"
+			)),
+		}
+	}
+
+	pub fn localize(&self, date: DateTime<Utc>) -> NaiveDateTime {
+		match self {
+			Self::Local | Self::Named(_) => date.with_timezone(&Local).naive_local(),
+			Self::Utc => date.naive_utc(),
+			Self::Fixed(offset) => date.with_timezone(offset).naive_local(),
+		}
+	}
+
+	pub fn timestamp(&self, date: &NaiveDateTime) -> i64 {
+		let timestamp = match self {
+			Self::Local | Self::Named(_) => Local
+				.from_local_datetime(date)
+				.earliest()
+				.map(|date| date.timestamp()),
+			Self::Utc => Some(date.and_utc().timestamp()),
+			Self::Fixed(offset) => offset
+				.from_local_datetime(date)
+				.earliest()
+				.map(|date| date.timestamp()),
+		};
+
+		timestamp.unwrap_or(date.and_utc().timestamp())
+	}
+}
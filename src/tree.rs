@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub struct Tree {
+	root: PathBuf,
+	dirs: BTreeMap<PathBuf, usize>,
+}
+
+fn label(count: usize) -> String {
+	match count {
+		0 => String::new(),
+		1 => " (1 file)".to_string(),
+		count => format!(" ({count} files)"),
+	}
+}
+
+impl Tree {
+	pub fn new(root: &Path) -> Self {
+		Self { root: root.to_path_buf(), dirs: BTreeMap::new() }
+	}
+
+	pub fn add(&mut self, dir: &Path) {
+		let Ok(relative) = dir.strip_prefix(&self.root) else {
+			return;
+		};
+
+		for ancestor in relative.ancestors().skip(1) {
+			self.dirs.entry(ancestor.to_path_buf()).or_default();
+		}
+
+		*self.dirs.entry(relative.to_path_buf()).or_default() += 1;
+	}
+
+	fn fmt_dir(&self, f: &mut Formatter, dir: &Path, indent: &str) -> fmt::Result {
+		let children: Vec<_> = self
+			.dirs
+			.iter()
+			.filter(|(child, _)| child.parent() == Some(dir))
+			.collect();
+
+		for (idx, (child, count)) in children.iter().enumerate() {
+			let last = idx + 1 == children.len();
+			let (branch, rest) = match last {
+				true => ("└── ", "    "),
+				false => ("├── ", "│   "),
+			};
+			let name = child.file_name().unwrap_or_default().to_string_lossy();
+
+			writeln!(f, "{indent}{branch}{name}{}", label(**count))?;
+
+			self.fmt_dir(f, child, &format!("{indent}{rest}"))?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Display for Tree {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let count = self.dirs.get(Path::new("")).copied().unwrap_or_default();
+		writeln!(f, "{}{}", self.root.display(), label(count))?;
+
+		self.fmt_dir(f, Path::new(""), "")
+	}
+}